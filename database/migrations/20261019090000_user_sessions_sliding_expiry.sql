@@ -0,0 +1,5 @@
+ALTER TABLE user_sessions
+    ADD COLUMN absolute_expires_at BIGINT UNSIGNED NOT NULL DEFAULT 0,
+    ADD COLUMN idle_timeout BIGINT UNSIGNED NOT NULL DEFAULT 0;
+
+UPDATE user_sessions SET absolute_expires_at = expires_at;
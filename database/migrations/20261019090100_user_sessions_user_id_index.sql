@@ -0,0 +1 @@
+CREATE INDEX idx_user_sessions_user_id ON user_sessions (user_id);
@@ -0,0 +1,7 @@
+ALTER TABLE user_sessions
+    ADD COLUMN last_seen_at BIGINT UNSIGNED NOT NULL DEFAULT 0,
+    ADD COLUMN ip_address VARCHAR(45) NULL,
+    ADD COLUMN user_agent VARCHAR(512) NULL,
+    ADD COLUMN device_label VARCHAR(255) NULL;
+
+UPDATE user_sessions SET last_seen_at = created_at;
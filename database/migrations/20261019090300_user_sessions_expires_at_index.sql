@@ -0,0 +1 @@
+CREATE INDEX idx_user_sessions_expires_at ON user_sessions (expires_at);
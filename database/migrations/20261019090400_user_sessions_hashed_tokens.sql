@@ -0,0 +1,7 @@
+-- Session ids become the SHA-256 hex digest of the bearer token
+ALTER TABLE user_sessions MODIFY session_id CHAR(64) NOT NULL;
+
+-- Hash existing plaintext tokens in place, so active sessions keep working
+UPDATE user_sessions
+SET session_id = SHA2(session_id, 256)
+WHERE CHAR_LENGTH(session_id) <> 64;
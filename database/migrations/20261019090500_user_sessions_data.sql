@@ -0,0 +1,2 @@
+-- JSON object of arbitrary session data
+ALTER TABLE user_sessions ADD COLUMN data TEXT NULL;
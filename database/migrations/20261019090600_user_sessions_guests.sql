@@ -0,0 +1,2 @@
+-- Guest sessions have no user until they log in
+ALTER TABLE user_sessions MODIFY user_id VARCHAR(255) NULL;
@@ -0,0 +1,8 @@
+CREATE TABLE user_remember_tokens (
+    selector CHAR(22) PRIMARY KEY,
+    hashed_validator CHAR(64) NOT NULL,
+    user_id VARCHAR(255) NOT NULL,
+    created_at BIGINT UNSIGNED NOT NULL,
+    expires_at BIGINT UNSIGNED NOT NULL,
+    INDEX idx_user_remember_tokens_user_id (user_id)
+);
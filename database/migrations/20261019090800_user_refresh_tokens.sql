@@ -0,0 +1,11 @@
+CREATE TABLE user_refresh_tokens (
+    token_id CHAR(64) PRIMARY KEY,
+    family_id CHAR(36) NOT NULL,
+    user_id VARCHAR(255) NOT NULL,
+    created_at BIGINT UNSIGNED NOT NULL,
+    expires_at BIGINT UNSIGNED NOT NULL,
+    rotated_at BIGINT UNSIGNED NULL,
+    INDEX idx_user_refresh_tokens_family_id (family_id),
+    INDEX idx_user_refresh_tokens_user_id (user_id),
+    INDEX idx_user_refresh_tokens_expires_at (expires_at)
+);
@@ -0,0 +1,12 @@
+CREATE TABLE revoked_tokens (
+    jti VARCHAR(64) PRIMARY KEY,
+    expires_at BIGINT UNSIGNED NOT NULL,
+    INDEX idx_revoked_tokens_expires_at (expires_at)
+);
+
+CREATE TABLE revoked_user_tokens (
+    user_id VARCHAR(255) PRIMARY KEY,
+    revoked_before BIGINT UNSIGNED NOT NULL,
+    expires_at BIGINT UNSIGNED NOT NULL,
+    INDEX idx_revoked_user_tokens_expires_at (expires_at)
+);
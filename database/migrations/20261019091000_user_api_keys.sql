@@ -0,0 +1,12 @@
+CREATE TABLE user_api_keys (
+    key_id CHAR(16) PRIMARY KEY,
+    hashed_secret CHAR(64) NOT NULL,
+    credentials_id CHAR(36) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    scopes TEXT NOT NULL,
+    created_at BIGINT UNSIGNED NOT NULL,
+    expires_at BIGINT UNSIGNED NULL,
+    last_used_at BIGINT UNSIGNED NULL,
+    revoked_at BIGINT UNSIGNED NULL,
+    INDEX idx_user_api_keys_credentials_id (credentials_id)
+);
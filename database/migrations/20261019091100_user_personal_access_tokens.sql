@@ -0,0 +1,14 @@
+CREATE TABLE user_personal_access_tokens (
+    token_id CHAR(36) PRIMARY KEY,
+    hashed_token CHAR(64) NOT NULL,
+    token_hint VARCHAR(16) NOT NULL,
+    credentials_id CHAR(36) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    scopes TEXT NOT NULL,
+    created_at BIGINT UNSIGNED NOT NULL,
+    expires_at BIGINT UNSIGNED NULL,
+    last_used_at BIGINT UNSIGNED NULL,
+    revoked_at BIGINT UNSIGNED NULL,
+    UNIQUE INDEX idx_user_personal_access_tokens_hashed_token (hashed_token),
+    INDEX idx_user_personal_access_tokens_credentials_id (credentials_id)
+);
@@ -0,0 +1,2 @@
+-- Rotated sessions live out their grace window but can not be rotated again
+ALTER TABLE user_sessions ADD COLUMN rotated_at BIGINT UNSIGNED NULL;
@@ -0,0 +1,4 @@
+-- Refresh token families expire a fixed time after login, however often they are refreshed
+ALTER TABLE user_refresh_tokens ADD COLUMN family_expires_at BIGINT UNSIGNED NOT NULL DEFAULT 0;
+
+UPDATE user_refresh_tokens SET family_expires_at = expires_at;
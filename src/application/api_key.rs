@@ -0,0 +1,154 @@
+use crate::config::Expiry;
+use crate::domain::entity::{find_invalid_scope, is_last_use_due, ApiKey, ApiKeyInfo};
+use crate::error::ApiKeyError;
+use crate::interface::{ApiKeyRepository, CredentialsRepository};
+use crate::AuthClient;
+use anyhow::Result;
+
+impl<C: CredentialsRepository + ApiKeyRepository> AuthClient<C> {
+    /// Creates an API key for the credentials, returning the raw key, which is only available now,
+    /// with what it grants. Pass None as the duration for a key that never expires.
+    /// Scopes must be non-empty and free of whitespace, or `ApiKeyError::InvalidScope` is returned.
+    pub async fn create_api_key(
+        &self,
+        credentials_id: &str,
+        name: &str,
+        scopes: &[&str],
+        duration: Option<Expiry>,
+    ) -> Result<(String, ApiKeyInfo)> {
+        if let Some(scope) = find_invalid_scope(scopes) {
+            return Err(ApiKeyError::InvalidScope {
+                scope: scope.to_string(),
+            }
+            .into());
+        }
+        self.gateway.find_credentials_by_id(credentials_id).await?;
+
+        let (api_key, raw_key) = ApiKey::new(credentials_id, name, scopes, duration.as_ref());
+        self.gateway.insert_api_key(&api_key).await?;
+
+        Ok((raw_key, api_key.info()))
+    }
+
+    /// Verifies a raw API key in constant time, refusing revoked and expired keys, and records when it was used.
+    /// Refusals are `ApiKeyError`s, while storage failures are passed through.
+    pub async fn verify_api_key(&self, raw_key: &str) -> Result<ApiKeyInfo> {
+        let (key_id, secret) = ApiKey::parse(raw_key).ok_or(ApiKeyError::Malformed)?;
+        let mut api_key = self
+            .gateway
+            .get_api_key(key_id)
+            .await?
+            .ok_or(ApiKeyError::Invalid)?;
+
+        if !api_key.match_secret(secret) {
+            return Err(ApiKeyError::Invalid.into());
+        }
+        if api_key.is_revoked() {
+            return Err(ApiKeyError::Revoked.into());
+        }
+        if api_key.is_expired() {
+            return Err(ApiKeyError::Expired.into());
+        }
+
+        let now = Expiry::now();
+        if is_last_use_due(api_key.last_used_at, now) {
+            self.gateway.touch_api_key(key_id, now).await?;
+            api_key.last_used_at = Some(now);
+        }
+
+        Ok(api_key.info())
+    }
+
+    /// Lists every API key of the credentials, including revoked and expired ones
+    pub async fn list_api_keys(&self, credentials_id: &str) -> Result<Vec<ApiKeyInfo>> {
+        let api_keys = self
+            .gateway
+            .get_api_keys_by_credentials_id(credentials_id)
+            .await?;
+
+        Ok(api_keys.iter().map(ApiKey::info).collect())
+    }
+
+    /// Revokes one of the credentials' API keys
+    pub async fn revoke_api_key(&self, credentials_id: &str, key_id: &str) -> Result<()> {
+        if self
+            .gateway
+            .revoke_api_key(credentials_id, key_id, Expiry::now())
+            .await?
+        {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("API key not found"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::mysql_configs;
+
+    #[tokio::test]
+    async fn test_mysql_api_keys() {
+        let db_configs = mysql_configs();
+        let auth = AuthClient::new_mysql_client(&db_configs.mysql_connection_string()).await;
+        let user_name = &format!("{}@mail.com", uuid::Uuid::new_v4());
+        let credentials_id = auth.register(user_name, "plokij1234!").await.unwrap();
+
+        let (raw_key, info) = auth
+            .create_api_key(&credentials_id, "billing", &["invoices:read"], None)
+            .await
+            .unwrap();
+        let verified = auth.verify_api_key(&raw_key).await.unwrap();
+        assert_eq!(verified.key_id, info.key_id);
+        assert!(verified.has_scope("invoices:read"));
+        assert!(verified.last_used_at.is_some());
+
+        // Wrong secrets, scopes with whitespace and unknown credentials are refused
+        let (key_id, _) = ApiKey::parse(&raw_key).unwrap();
+        let refused = auth
+            .verify_api_key(&format!("bk_{key_id}_guessed"))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            refused.downcast_ref::<ApiKeyError>(),
+            Some(&ApiKeyError::Invalid)
+        );
+        let refused = auth
+            .create_api_key(&credentials_id, "ci", &["invoices read"], None)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            refused.downcast_ref::<ApiKeyError>(),
+            Some(&ApiKeyError::InvalidScope {
+                scope: "invoices read".to_string()
+            })
+        );
+        assert!(auth
+            .create_api_key("missing-credentials", "ci", &[], None)
+            .await
+            .is_err());
+
+        assert_eq!(auth.list_api_keys(&credentials_id).await.unwrap().len(), 1);
+        auth.revoke_api_key(&credentials_id, &info.key_id)
+            .await
+            .unwrap();
+        let refused = auth.verify_api_key(&raw_key).await.unwrap_err();
+        assert_eq!(
+            refused.downcast_ref::<ApiKeyError>(),
+            Some(&ApiKeyError::Revoked)
+        );
+
+        // Destroying the credentials deletes their keys
+        let (raw_key, _) = auth
+            .create_api_key(&credentials_id, "reports", &[], None)
+            .await
+            .unwrap();
+        auth.destroy_credentials(user_name).await.unwrap();
+        let refused = auth.verify_api_key(&raw_key).await.unwrap_err();
+        assert_eq!(
+            refused.downcast_ref::<ApiKeyError>(),
+            Some(&ApiKeyError::Invalid)
+        );
+    }
+}
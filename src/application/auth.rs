@@ -1,4 +1,6 @@
+use crate::domain::authorization::parse_basic;
 use crate::domain::entity::{Credentials, CredentialsId};
+use crate::domain::jwt::JwtIssuer;
 use crate::infrastructure::gateway;
 use crate::{
     application::interface::CredentialsRepository,
@@ -22,11 +24,9 @@ impl<C: CredentialsRepository> AuthClient<C> {
     /// Register a new user and insert them into the database if user does not already exist
     pub async fn register(&self, user_name: &str, raw_password: &str) -> Result<CredentialsId> {
         match self.gateway.find_credentials_by_user_name(user_name).await {
-            Ok(_) => {
-                return Err(anyhow::anyhow!(
-                    "Registration failed, credentials already exist"
-                ))
-            }
+            Ok(_) => Err(anyhow::anyhow!(
+                "Registration failed, credentials already exist"
+            )),
             Err(_) => {
                 let hashed_password = hash_raw_password(raw_password);
 
@@ -37,23 +37,61 @@ impl<C: CredentialsRepository> AuthClient<C> {
                     .await
                     .context("Registration failed, repository error")?;
 
-                return Ok(credentials.credentials_id);
+                Ok(credentials.credentials_id)
             }
-        };
+        }
     }
 
-    /// Matches credentials provided by the user with the what is in the database
-    pub async fn verify_credentials(&self, user_name: &str, raw_password: &str) -> Result<()> {
+    /// Loads the credentials and matches the password against them, returning the row that was checked
+    async fn find_verified_credentials(
+        &self,
+        user_name: &str,
+        raw_password: &str,
+    ) -> Result<Credentials> {
         let creds = self
             .gateway
-            .find_credentials_by_user_name(&user_name)
+            .find_credentials_by_user_name(user_name)
             .await?;
 
         verify_password(raw_password, &creds.hashed_password)
-            .context("Username or Password did not match")
+            .context("Username or Password did not match")?;
+        Ok(creds)
+    }
+
+    /// Matches credentials provided by the user with the what is in the database
+    pub async fn verify_credentials(&self, user_name: &str, raw_password: &str) -> Result<()> {
+        self.find_verified_credentials(user_name, raw_password)
+            .await?;
+        Ok(())
+    }
+
+    /// Verifies the credentials of an `Authorization: Basic` header value, returning their id
+    pub async fn verify_basic_authorization(&self, header: &str) -> Result<CredentialsId> {
+        let (user_name, raw_password) = parse_basic(header)?;
+        let creds = self
+            .find_verified_credentials(&user_name, &raw_password)
+            .await?;
+
+        Ok(creds.credentials_id)
     }
 
-    /// Deletes credentials from table
+    /// Verifies the credentials, then issues a JWT access token whose subject is the credentials id
+    pub async fn issue_access_token(
+        &self,
+        user_name: &str,
+        raw_password: &str,
+        issuer: &JwtIssuer,
+    ) -> Result<String> {
+        let creds = self
+            .find_verified_credentials(user_name, raw_password)
+            .await?;
+
+        issuer
+            .issue(&creds.credentials_id)
+            .context("Failed to issue access token")
+    }
+
+    /// Deletes credentials from table, along with their API keys and personal access tokens
     pub async fn destroy_credentials(&self, user_name: &str) -> Result<()> {
         self.gateway
             .delete_credentials_by_user_name(user_name)
@@ -84,6 +122,7 @@ impl<C: CredentialsRepository> AuthClient<C> {
 mod tests {
     use super::*;
     use crate::helpers::mysql_configs;
+    use base64::Engine;
 
     #[tokio::test]
     async fn test_mysql_auth() {
@@ -99,5 +138,19 @@ mod tests {
 
         // login attempt
         auth.verify_credentials(email, password).await.unwrap();
+
+        // login attempt with a Basic Authorization header
+        let header = format!(
+            "Basic {}",
+            base64::engine::general_purpose::STANDARD.encode(format!("{email}:{password}"))
+        );
+        assert_eq!(
+            auth.verify_basic_authorization(&header).await.unwrap(),
+            creds_id
+        );
+        assert!(auth
+            .verify_basic_authorization("Basic bm9wZTpub3Bl")
+            .await
+            .is_err());
     }
 }
@@ -0,0 +1,128 @@
+use crate::config::Expiry;
+use crate::domain::entity::Session;
+use crate::infrastructure::gateway::cookie_store::{CookieKeyRing, CookieStore};
+use crate::SessionClient;
+use anyhow::Result;
+
+/// Sessions sealed into encrypted cookies, with no server side storage.
+/// Whenever a returned `Session::token` is set, it is the cookie value to hand the client.
+///
+/// Nothing on the server knows which cookies are out there, so a cookie stays valid until it
+/// expires. That rules out what needs to count or retire sessions: `session_limit` must stay None,
+/// or starting a session fails, and there is no rotation, upgrading or listing of sessions.
+/// `token_generator` only seeds `Session::session_id`, and `rotation_grace` is unused.
+impl SessionClient<CookieStore> {
+    pub fn new_cookie_client(key_ring: CookieKeyRing) -> SessionClient<CookieStore> {
+        SessionClient::new(CookieStore::new(key_ring))
+    }
+
+    /// Seals a freshly issued session into `Session::token`
+    fn seal_new_session(&self, mut session: Session) -> Result<Session> {
+        if self.session_limit.is_some() {
+            return Err(anyhow::anyhow!(
+                "Session limits need a SessionRepository, cookie sessions can not be counted"
+            ));
+        }
+
+        session.token = Some(self.gateway.seal(&session)?);
+        Ok(session)
+    }
+
+    /// Issues a new session sealed into `Session::token`
+    pub async fn start_session(&self, user_id: &str, duration: Expiry) -> Result<Session> {
+        self.seal_new_session(self.new_session(&duration, user_id))
+    }
+
+    /// Issues a session that expires after being idle for `idle`, but never outlives `absolute`
+    pub async fn start_sliding_session(
+        &self,
+        user_id: &str,
+        idle: Expiry,
+        absolute: Expiry,
+    ) -> Result<Session> {
+        self.seal_new_session(
+            self.new_session(&absolute, user_id)
+                .with_idle_timeout(&idle),
+        )
+    }
+
+    /// Opens and validates the session cookie, enforcing its expiration.
+    /// When the session changed, e.g. its idle expiration slid forward or flash messages were taken,
+    /// `Session::token` holds the resealed cookie value which must be sent back to the client.
+    pub async fn validate_session(&self, session_token: &str) -> Result<Session> {
+        let mut session = self.gateway.open(session_token)?;
+
+        if session.is_expired() {
+            return Err(anyhow::anyhow!("Session expired"));
+        }
+
+        let touched = session.touch(self.touch_interval);
+        session.flashes = session.take_flashes()?;
+
+        if touched || session.data.is_modified() {
+            self.save_session(&mut session).await?;
+        }
+
+        Ok(session)
+    }
+
+    /// Reseals the session into `Session::token`, to be sent back to the client
+    pub async fn save_session(&self, session: &mut Session) -> Result<()> {
+        session.data.mark_saved();
+        session.token = Some(self.gateway.seal(session)?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{SessionLimit, SessionLimitPolicy};
+    use crate::cookie_store::CookieKey;
+    use crate::entity::FlashLevel;
+
+    #[tokio::test]
+    async fn test_cookie_session() {
+        let sesh =
+            SessionClient::new_cookie_client(CookieKeyRing::new(CookieKey::new("k1", &[3; 32])));
+
+        let session = sesh.start_session("user-id", Expiry::Day(1)).await.unwrap();
+        let cookie_value = session.token.clone().unwrap();
+
+        let validated = sesh.validate_session(&cookie_value).await.unwrap();
+        assert_eq!(validated.session_id, session.session_id);
+        assert_eq!(validated.user_id.as_deref(), Some("user-id"));
+        assert!(validated.token.is_none());
+
+        // Changes are carried in the resealed cookie
+        let mut validated = validated;
+        validated
+            .push_flash(FlashLevel::Info, "Welcome back")
+            .unwrap();
+        sesh.save_session(&mut validated).await.unwrap();
+        let next_request = sesh
+            .validate_session(validated.token.as_deref().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(next_request.flashes.len(), 1);
+        assert!(next_request.token.is_some());
+
+        // Expired sessions are refused
+        let mut expired = sesh.start_session("user-id", Expiry::Day(1)).await.unwrap();
+        expired.expires_at = Expiry::now() - 10;
+        let expired_cookie = sesh.gateway.seal(&expired).unwrap();
+        assert!(sesh.validate_session(&expired_cookie).await.is_err());
+
+        // Cookie sessions can not be counted, so a session limit is refused
+        let mut sesh = sesh;
+        sesh.session_limit = Some(SessionLimit {
+            max_sessions: 5,
+            policy: SessionLimitPolicy::Reject,
+        });
+        assert!(sesh.start_session("user-id", Expiry::Day(1)).await.is_err());
+        assert!(sesh
+            .start_sliding_session("user-id", Expiry::Second(3600), Expiry::Day(1))
+            .await
+            .is_err());
+    }
+}
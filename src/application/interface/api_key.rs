@@ -0,0 +1,22 @@
+use crate::domain::entity::ApiKey;
+use anyhow::Result;
+
+#[trait_variant::make(HttpService: Send)]
+pub trait ApiKeyRepository: Send + Sync {
+    async fn insert_api_key(&self, api_key: &ApiKey) -> Result<()>;
+
+    /// Returns None when no key has the id
+    async fn get_api_key(&self, key_id: &str) -> Result<Option<ApiKey>>;
+
+    async fn get_api_keys_by_credentials_id(&self, credentials_id: &str) -> Result<Vec<ApiKey>>;
+
+    async fn touch_api_key(&self, key_id: &str, last_used_at: u64) -> Result<()>;
+
+    /// Revokes the key only if it belongs to the credentials, returning false when it does not
+    async fn revoke_api_key(
+        &self,
+        credentials_id: &str,
+        key_id: &str,
+        revoked_at: u64,
+    ) -> Result<bool>;
+}
@@ -13,7 +13,10 @@ pub trait CredentialsRepository: Send + Sync {
 
     async fn update_user_password(&self, user_name: &str, new_password: &str) -> Result<()>;
 
+    /// Deletes the credentials along with the API keys and personal access tokens they own,
+    /// so nothing authenticates as them afterwards
     async fn delete_credentials_by_user_name(&self, user_name: &str) -> Result<()>;
 
+    /// Deletes the credentials along with the API keys and personal access tokens they own
     async fn delete_credentials_by_id(&self, id: &str) -> Result<()>;
 }
@@ -1,5 +1,20 @@
+mod api_key;
+pub use api_key::ApiKeyRepository;
+
 mod credentials;
 pub use credentials::CredentialsRepository;
 
+mod personal_access_token;
+pub use personal_access_token::PersonalAccessTokenRepository;
+
+mod refresh_token;
+pub use refresh_token::{LocalRefreshTokenRepository, RefreshTokenRepository};
+
+mod remember_token;
+pub use remember_token::RememberTokenRepository;
+
+mod revocation;
+pub use revocation::{LocalRevocationRepository, RevocationRepository};
+
 mod session;
-pub use session::SessionRepository;
+pub use session::{LocalSessionRepository, SessionRepository};
@@ -0,0 +1,34 @@
+use crate::domain::entity::PersonalAccessToken;
+use anyhow::Result;
+
+#[trait_variant::make(HttpService: Send)]
+pub trait PersonalAccessTokenRepository: Send + Sync {
+    async fn insert_personal_access_token(&self, token: &PersonalAccessToken) -> Result<()>;
+
+    /// Returns None when no token has the digest
+    async fn get_personal_access_token_by_hash(
+        &self,
+        hashed_token: &str,
+    ) -> Result<Option<PersonalAccessToken>>;
+
+    async fn get_personal_access_tokens_by_credentials_id(
+        &self,
+        credentials_id: &str,
+    ) -> Result<Vec<PersonalAccessToken>>;
+
+    async fn touch_personal_access_token(&self, token_id: &str, last_used_at: u64) -> Result<()>;
+
+    /// Revokes the token only if it belongs to the credentials, returning false when it does not
+    async fn revoke_personal_access_token(
+        &self,
+        credentials_id: &str,
+        token_id: &str,
+        revoked_at: u64,
+    ) -> Result<bool>;
+
+    async fn revoke_personal_access_tokens_by_credentials_id(
+        &self,
+        credentials_id: &str,
+        revoked_at: u64,
+    ) -> Result<()>;
+}
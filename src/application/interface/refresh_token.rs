@@ -0,0 +1,27 @@
+use crate::domain::entity::RefreshToken;
+use anyhow::Result;
+
+/// Stores refresh tokens. Implementations return `Send` futures, so their expired rows can be
+/// purged from a Tokio task, see `TokenClient::spawn_purge_task`.
+#[trait_variant::make(RefreshTokenRepository: Send)]
+pub trait LocalRefreshTokenRepository: Send + Sync {
+    async fn insert_refresh_token(&self, token: &RefreshToken) -> Result<()>;
+
+    async fn get_refresh_token(&self, token_id: &str) -> Result<RefreshToken>;
+
+    /// Marks the token as rotated and stores its successor, only if it was not already rotated.
+    /// Returns false when a concurrent refresh already used it.
+    async fn rotate_refresh_token(
+        &self,
+        token_id: &str,
+        rotated_at: u64,
+        successor: &RefreshToken,
+    ) -> Result<bool>;
+
+    async fn delete_refresh_token_family(&self, family_id: &str) -> Result<()>;
+
+    async fn delete_refresh_tokens_by_user_id(&self, user_id: &str) -> Result<()>;
+
+    /// Deletes up to `batch_size` tokens that expired before `now`, returning how many were deleted
+    async fn delete_expired_refresh_tokens(&self, now: u64, batch_size: u64) -> Result<u64>;
+}
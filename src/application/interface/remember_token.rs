@@ -0,0 +1,22 @@
+use crate::domain::entity::RememberToken;
+use anyhow::Result;
+
+#[trait_variant::make(HttpService: Send)]
+pub trait RememberTokenRepository: Send + Sync {
+    async fn insert_remember_token(&self, token: &RememberToken) -> Result<()>;
+
+    async fn get_remember_token(&self, selector: &str) -> Result<RememberToken>;
+
+    /// Swaps the validator only if it still matches `hashed_validator`, returning false when a
+    /// concurrent use already rotated it
+    async fn rotate_remember_token(
+        &self,
+        selector: &str,
+        hashed_validator: &str,
+        new_hashed_validator: &str,
+    ) -> Result<bool>;
+
+    async fn delete_remember_token(&self, selector: &str) -> Result<()>;
+
+    async fn delete_remember_tokens_by_user_id(&self, user_id: &str) -> Result<()>;
+}
@@ -0,0 +1,26 @@
+use anyhow::Result;
+
+/// Stores access token revocations. Implementations return `Send` futures, so their expired rows can be
+/// purged from a Tokio task, see `TokenClient::spawn_purge_task`.
+#[trait_variant::make(RevocationRepository: Send)]
+pub trait LocalRevocationRepository: Send + Sync {
+    /// Denies the token id until `expires_at`, after which the token would have expired anyway
+    async fn revoke_token(&self, jti: &str, expires_at: u64) -> Result<()>;
+
+    async fn is_token_revoked(&self, jti: &str) -> Result<bool>;
+
+    /// Denies every token for the user issued before `issued_before` until `expires_at`,
+    /// keeping the latest cutoff when one already exists
+    async fn revoke_user_tokens_before(
+        &self,
+        user_id: &str,
+        issued_before: u64,
+        expires_at: u64,
+    ) -> Result<()>;
+
+    /// The cutoff set by `revoke_user_tokens_before`, None when the user has none
+    async fn get_user_revoked_before(&self, user_id: &str) -> Result<Option<u64>>;
+
+    /// Deletes up to `batch_size` revocations that expired before `now`, returning how many were deleted
+    async fn delete_expired_revocations(&self, now: u64, batch_size: u64) -> Result<u64>;
+}
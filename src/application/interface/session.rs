@@ -1,11 +1,48 @@
-use crate::domain::entity::{Session, SessionToken};
+use crate::domain::config::SessionLimit;
+use crate::domain::entity::{Session, SessionId};
 use anyhow::Result;
 
-#[trait_variant::make(HttpService: Send)]
-pub trait SessionRepository: Send + Sync {
+/// Stores sessions. Implementations return `Send` futures, so clients over any repository can be
+/// shared across Tokio tasks, see `SessionClient::spawn_purge_task`.
+#[trait_variant::make(SessionRepository: Send)]
+pub trait LocalSessionRepository: Send + Sync {
     async fn insert_session(&self, session: &Session) -> Result<()>;
 
-    async fn get_session_by_id(&self, session_id: &SessionToken) -> Result<Session>;
+    /// Inserts the session while holding the user to `limit`, which must be race safe under
+    /// concurrent logins. Fails with `SessionError::LimitExceeded` when the policy rejects it.
+    async fn insert_session_with_limit(
+        &self,
+        session: &Session,
+        limit: &SessionLimit,
+    ) -> Result<()>;
 
-    async fn delete_session(&self, session_id: &SessionToken) -> Result<()>;
+    async fn get_session_by_id(&self, session_id: &SessionId) -> Result<Session>;
+
+    async fn get_sessions_by_user_id(&self, user_id: &str) -> Result<Vec<Session>>;
+
+    async fn touch_session(&self, session: &Session) -> Result<()>;
+
+    async fn update_session_data(&self, session: &Session) -> Result<()>;
+
+    /// Atomically inserts `new_session` and retires the old one, either deleting it or marking it
+    /// rotated and letting it resolve until `grace_expires_at`. Fails when the old session is
+    /// missing or was already rotated, so a token can only be rotated once.
+    /// Given a `limit`, the new session's user is held to it as by `insert_session_with_limit`,
+    /// within the same atomic step.
+    async fn rotate_session(
+        &self,
+        old_session_id: &SessionId,
+        new_session: &Session,
+        grace_expires_at: Option<u64>,
+        limit: Option<&SessionLimit>,
+    ) -> Result<()>;
+
+    async fn delete_session(&self, session_id: &SessionId) -> Result<()>;
+
+    /// Deletes up to `batch_size` sessions that expired before `now`, returning how many were deleted
+    async fn delete_expired_sessions(&self, now: u64, batch_size: u64) -> Result<u64>;
+
+    async fn delete_sessions_by_user_id(&self, user_id: &str) -> Result<()>;
+
+    async fn delete_sessions_except(&self, user_id: &str, keep: &SessionId) -> Result<()>;
 }
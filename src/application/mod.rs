@@ -1,6 +1,18 @@
 mod auth;
 pub use auth::AuthClient;
+mod api_key;
 pub mod interface;
+mod personal_access_token;
 
 mod session;
 pub use session::SessionClient;
+
+mod token;
+pub use token::TokenClient;
+mod cookie_session;
+mod revocation;
+
+mod remember;
+
+mod purge;
+pub use purge::{PurgeConfig, PurgeHandle};
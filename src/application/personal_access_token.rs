@@ -0,0 +1,199 @@
+use crate::config::Expiry;
+use crate::domain::entity::{
+    find_invalid_scope, hash_secret, is_last_use_due, PersonalAccessToken, PersonalAccessTokenInfo,
+};
+use crate::error::PersonalAccessTokenError;
+use crate::interface::{CredentialsRepository, PersonalAccessTokenRepository};
+use crate::AuthClient;
+use anyhow::Result;
+
+impl<C: CredentialsRepository + PersonalAccessTokenRepository> AuthClient<C> {
+    /// Creates a personal access token for the credentials, returning the raw token, which is
+    /// only available now, with what it grants. Pass None as the duration for a token that never expires.
+    /// Scopes must be non-empty and free of whitespace, or `PersonalAccessTokenError::InvalidScope` is returned.
+    pub async fn create_personal_access_token(
+        &self,
+        credentials_id: &str,
+        name: &str,
+        scopes: &[&str],
+        duration: Option<Expiry>,
+    ) -> Result<(String, PersonalAccessTokenInfo)> {
+        if let Some(scope) = find_invalid_scope(scopes) {
+            return Err(PersonalAccessTokenError::InvalidScope {
+                scope: scope.to_string(),
+            }
+            .into());
+        }
+        self.gateway.find_credentials_by_id(credentials_id).await?;
+
+        let (token, raw_token) =
+            PersonalAccessToken::new(credentials_id, name, scopes, duration.as_ref());
+        self.gateway.insert_personal_access_token(&token).await?;
+
+        Ok((raw_token, token.info()))
+    }
+
+    /// Verifies a raw token and that it was granted every `required_scopes`, recording when it was used.
+    /// Tokens failing the checksum are refused without a lookup. Refusals are `PersonalAccessTokenError`s,
+    /// while storage failures are passed through.
+    pub async fn verify_personal_access_token(
+        &self,
+        raw_token: &str,
+        required_scopes: &[&str],
+    ) -> Result<PersonalAccessTokenInfo> {
+        if !PersonalAccessToken::verify_checksum(raw_token) {
+            return Err(PersonalAccessTokenError::Malformed.into());
+        }
+
+        let mut token = self
+            .gateway
+            .get_personal_access_token_by_hash(&hash_secret(raw_token))
+            .await?
+            .ok_or(PersonalAccessTokenError::Invalid)?;
+
+        if token.is_revoked() {
+            return Err(PersonalAccessTokenError::Revoked.into());
+        }
+        if token.is_expired() {
+            return Err(PersonalAccessTokenError::Expired.into());
+        }
+
+        let missing = token.info().missing_scopes(required_scopes);
+        if !missing.is_empty() {
+            return Err(PersonalAccessTokenError::MissingScopes { missing }.into());
+        }
+
+        let now = Expiry::now();
+        if is_last_use_due(token.last_used_at, now) {
+            self.gateway
+                .touch_personal_access_token(&token.token_id, now)
+                .await?;
+            token.last_used_at = Some(now);
+        }
+
+        Ok(token.info())
+    }
+
+    /// Lists every personal access token of the credentials, including revoked and expired ones
+    pub async fn list_personal_access_tokens(
+        &self,
+        credentials_id: &str,
+    ) -> Result<Vec<PersonalAccessTokenInfo>> {
+        let tokens = self
+            .gateway
+            .get_personal_access_tokens_by_credentials_id(credentials_id)
+            .await?;
+
+        Ok(tokens.iter().map(PersonalAccessToken::info).collect())
+    }
+
+    /// Revokes one of the credentials' personal access tokens
+    pub async fn revoke_personal_access_token(
+        &self,
+        credentials_id: &str,
+        token_id: &str,
+    ) -> Result<()> {
+        if self
+            .gateway
+            .revoke_personal_access_token(credentials_id, token_id, Expiry::now())
+            .await?
+        {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("Personal access token not found"))
+        }
+    }
+
+    /// Revokes every personal access token of the credentials, e.g. after a password change
+    pub async fn revoke_all_personal_access_tokens(&self, credentials_id: &str) -> Result<()> {
+        self.gateway
+            .revoke_personal_access_tokens_by_credentials_id(credentials_id, Expiry::now())
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::mysql_configs;
+
+    #[tokio::test]
+    async fn test_mysql_personal_access_tokens() {
+        let db_configs = mysql_configs();
+        let auth = AuthClient::new_mysql_client(&db_configs.mysql_connection_string()).await;
+        let user_name = &format!("{}@mail.com", uuid::Uuid::new_v4());
+        let credentials_id = auth.register(user_name, "plokij1234!").await.unwrap();
+
+        let (raw_token, info) = auth
+            .create_personal_access_token(
+                &credentials_id,
+                "deploy script",
+                &["repo:read", "repo:write"],
+                Some(Expiry::Day(90)),
+            )
+            .await
+            .unwrap();
+        let verified = auth
+            .verify_personal_access_token(&raw_token, &["repo:read"])
+            .await
+            .unwrap();
+        assert_eq!(verified.token_id, info.token_id);
+        assert!(verified.last_used_at.is_some());
+
+        // Missing scopes are reported
+        let refused = auth
+            .verify_personal_access_token(&raw_token, &["repo:read", "admin"])
+            .await
+            .unwrap_err();
+        assert_eq!(
+            refused.downcast_ref::<PersonalAccessTokenError>(),
+            Some(&PersonalAccessTokenError::MissingScopes {
+                missing: vec!["admin".to_string()]
+            })
+        );
+
+        let listed = auth
+            .list_personal_access_tokens(&credentials_id)
+            .await
+            .unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].token_hint, &raw_token[..9]);
+
+        auth.revoke_all_personal_access_tokens(&credentials_id)
+            .await
+            .unwrap();
+        let refused = auth
+            .verify_personal_access_token(&raw_token, &[])
+            .await
+            .unwrap_err();
+        assert_eq!(
+            refused.downcast_ref::<PersonalAccessTokenError>(),
+            Some(&PersonalAccessTokenError::Revoked)
+        );
+
+        // Scopes with whitespace are refused, and destroying the credentials deletes their tokens
+        let refused = auth
+            .create_personal_access_token(&credentials_id, "ci", &["repo read"], None)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            refused.downcast_ref::<PersonalAccessTokenError>(),
+            Some(&PersonalAccessTokenError::InvalidScope {
+                scope: "repo read".to_string()
+            })
+        );
+        let (raw_token, _) = auth
+            .create_personal_access_token(&credentials_id, "ci", &[], None)
+            .await
+            .unwrap();
+        auth.destroy_credentials(user_name).await.unwrap();
+        let refused = auth
+            .verify_personal_access_token(&raw_token, &[])
+            .await
+            .unwrap_err();
+        assert_eq!(
+            refused.downcast_ref::<PersonalAccessTokenError>(),
+            Some(&PersonalAccessTokenError::Invalid)
+        );
+    }
+}
@@ -0,0 +1,271 @@
+use crate::config::Expiry;
+use crate::interface::{RefreshTokenRepository, RevocationRepository, SessionRepository};
+use crate::{SessionClient, TokenClient};
+use anyhow::Result;
+use std::future::Future;
+use std::time::Duration;
+use tokio::{sync::watch, task::JoinHandle};
+
+/// How often and how aggressively a background task purges expired rows
+#[derive(Debug, Clone, Copy)]
+pub struct PurgeConfig {
+    /// Time between purge runs
+    pub interval: Duration,
+    /// Rows deleted per query, keeping each delete's locks short lived, must be above zero
+    pub batch_size: u64,
+}
+
+impl Default for PurgeConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(300),
+            batch_size: 1_000,
+        }
+    }
+}
+
+/// Handle to a running purge task, the task stops when this is shut down or dropped
+pub struct PurgeHandle {
+    shutdown: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+impl PurgeHandle {
+    /// Signals the task to stop after its current batch and waits for it to finish
+    pub async fn shutdown(self) {
+        let _ = self.shutdown.send(true);
+        let _ = self.task.await;
+    }
+}
+
+/// Calls `delete_batch` until a batch comes up short, returning how many rows were deleted in total.
+/// A zero batch size is refused, as no batch could ever come up short.
+pub(crate) async fn purge_in_batches<F, Fut>(batch_size: u64, mut delete_batch: F) -> Result<u64>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<u64>>,
+{
+    if batch_size == 0 {
+        return Err(anyhow::anyhow!("Purge batch size must be above zero"));
+    }
+
+    let mut purged = 0;
+    loop {
+        let deleted = delete_batch().await?;
+        purged += deleted;
+
+        if deleted < batch_size {
+            return Ok(purged);
+        }
+    }
+}
+
+/// Like `purge_in_batches`, but stops between batches once the task is shut down
+async fn purge_until_shutdown<F, Fut>(
+    shutdown: &watch::Receiver<bool>,
+    batch_size: u64,
+    mut delete_batch: F,
+) -> Result<u64>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<u64>>,
+{
+    purge_in_batches(batch_size, || {
+        let stopped = *shutdown.borrow();
+        let batch = (!stopped).then(&mut delete_batch);
+        async move {
+            match batch {
+                Some(batch) => batch.await,
+                None => Ok(0),
+            }
+        }
+    })
+    .await
+}
+
+/// Spawns a Tokio task running `purge` on every tick of the interval until shut down.
+/// `purge` gets the shutdown receiver, to pass to `purge_until_shutdown`.
+fn spawn_purge<F, Fut>(config: PurgeConfig, purge: F) -> Result<PurgeHandle>
+where
+    F: Fn(watch::Receiver<bool>) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send,
+{
+    if config.interval.is_zero() || config.batch_size == 0 {
+        return Err(anyhow::anyhow!(
+            "Purge interval and batch size must be above zero"
+        ));
+    }
+
+    let (shutdown, mut shutdown_rx) = watch::channel(false);
+    let task = tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(config.interval);
+
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.changed() => break,
+                _ = ticker.tick() => purge(shutdown_rx.clone()).await,
+            }
+        }
+    });
+
+    Ok(PurgeHandle { shutdown, task })
+}
+
+impl<S: SessionRepository + Clone + 'static> SessionClient<S> {
+    /// Spawns a Tokio task that periodically deletes expired sessions in batches.
+    /// Fails when the interval or batch size is zero.
+    pub fn spawn_purge_task(&self, config: PurgeConfig) -> Result<PurgeHandle> {
+        let gateway = self.gateway.clone();
+
+        spawn_purge(config, move |shutdown| {
+            let gateway = gateway.clone();
+            async move {
+                // Errors are transient from here, the next tick simply tries again
+                let _ = purge_until_shutdown(&shutdown, config.batch_size, || {
+                    gateway.delete_expired_sessions(Expiry::now(), config.batch_size)
+                })
+                .await;
+            }
+        })
+    }
+}
+
+impl<S: RefreshTokenRepository + RevocationRepository + Clone + 'static> TokenClient<S> {
+    /// Spawns a Tokio task that periodically deletes expired refresh tokens and access token
+    /// revocations in batches. Fails when the interval or batch size is zero.
+    pub fn spawn_purge_task(&self, config: PurgeConfig) -> Result<PurgeHandle> {
+        let gateway = self.gateway.clone();
+
+        spawn_purge(config, move |shutdown| {
+            let gateway = gateway.clone();
+            async move {
+                // Errors are transient from here, the next tick simply tries again
+                let _ = purge_until_shutdown(&shutdown, config.batch_size, || {
+                    gateway.delete_expired_refresh_tokens(Expiry::now(), config.batch_size)
+                })
+                .await;
+                let _ = purge_until_shutdown(&shutdown, config.batch_size, || {
+                    gateway.delete_expired_revocations(Expiry::now(), config.batch_size)
+                })
+                .await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entity::{hash_session_token, RefreshToken, Session};
+    use crate::domain::jwt::{JwtIssuer, JwtKey};
+    use crate::helpers::mysql_configs;
+    use crate::memory::MemoryGateway;
+
+    #[tokio::test]
+    async fn test_mysql_purge_task() {
+        let db_configs = mysql_configs();
+        let sesh = SessionClient::new_mysql_client(&db_configs.mysql_connection_string()).await;
+        let user_id = &uuid::Uuid::new_v4().to_string();
+
+        for _ in 0..3 {
+            sesh.start_session(user_id, Expiry::Second(0))
+                .await
+                .unwrap();
+        }
+        let live = sesh.start_session(user_id, Expiry::Day(1)).await.unwrap();
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        let handle = sesh
+            .spawn_purge_task(PurgeConfig {
+                interval: Duration::from_millis(100),
+                batch_size: 2,
+            })
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        handle.shutdown().await;
+
+        let sessions = sesh.get_user_sessions(user_id).await.unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].session_id, live.session_id);
+    }
+
+    #[tokio::test]
+    async fn test_purge_task() {
+        let sesh = SessionClient::new(MemoryGateway::new());
+        for _ in 0..3 {
+            let mut expired = Session::new(&Expiry::Day(1), "user-id");
+            expired.expires_at = Expiry::now() - 10;
+            sesh.gateway.insert_session(&expired).await.unwrap();
+        }
+        let live = sesh.start_session("user-id", Expiry::Day(1)).await.unwrap();
+
+        assert!(sesh
+            .spawn_purge_task(PurgeConfig {
+                interval: Duration::from_millis(100),
+                batch_size: 0,
+            })
+            .is_err());
+        assert!(sesh.purge_expired_sessions(0).await.is_err());
+
+        let handle = sesh
+            .spawn_purge_task(PurgeConfig {
+                interval: Duration::from_millis(100),
+                batch_size: 2,
+            })
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        handle.shutdown().await;
+
+        let sessions = sesh.get_user_sessions("user-id").await.unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].session_id, live.session_id);
+    }
+
+    #[tokio::test]
+    async fn test_token_purge_task() {
+        let issuer = JwtIssuer::new(
+            JwtKey::hs256("2024-01", b"shared-secret"),
+            "brize",
+            "mobile",
+            Expiry::Second(60),
+        );
+        let tokens = TokenClient::new(MemoryGateway::new(), issuer);
+
+        let (mut expired, _) = RefreshToken::new(&Expiry::Day(1), "user-id");
+        expired.expires_at = Expiry::now() - 10;
+        tokens.gateway.insert_refresh_token(&expired).await.unwrap();
+        let live = tokens.issue_tokens("user-id").await.unwrap();
+        let live_id = hash_session_token(&live.refresh_token);
+
+        let gateway = &tokens.gateway;
+        gateway
+            .revoke_token("expired-jti", Expiry::now() - 10)
+            .await
+            .unwrap();
+        gateway
+            .revoke_token("live-jti", Expiry::now() + 60)
+            .await
+            .unwrap();
+
+        assert!(tokens
+            .spawn_purge_task(PurgeConfig {
+                interval: Duration::ZERO,
+                batch_size: 1,
+            })
+            .is_err());
+
+        let handle = tokens
+            .spawn_purge_task(PurgeConfig {
+                interval: Duration::from_millis(100),
+                batch_size: 1,
+            })
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        handle.shutdown().await;
+
+        assert!(gateway.get_refresh_token(&expired.token_id).await.is_err());
+        assert!(gateway.get_refresh_token(&live_id).await.is_ok());
+        assert!(!gateway.is_token_revoked("expired-jti").await.unwrap());
+        assert!(gateway.is_token_revoked("live-jti").await.unwrap());
+    }
+}
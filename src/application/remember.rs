@@ -0,0 +1,145 @@
+use crate::config::Expiry;
+use crate::domain::entity::{RememberToken, Session};
+use crate::error::SessionError;
+use crate::interface::{RememberTokenRepository, SessionRepository};
+use crate::SessionClient;
+use anyhow::Result;
+
+impl<S: SessionRepository + RememberTokenRepository> SessionClient<S> {
+    /// Starts a "keep me signed in" series for the user, returning the cookie value
+    pub async fn remember_user(&self, user_id: &str, duration: Expiry) -> Result<String> {
+        let (token, cookie_value) = RememberToken::new(&duration, user_id);
+        self.gateway.insert_remember_token(&token).await?;
+        Ok(cookie_value)
+    }
+
+    /// Redeems a remember-me cookie for a fresh session, returning it with the rotated cookie value.
+    /// Presenting an already rotated validator means the cookie was copied, so the whole series
+    /// is revoked and `SessionError::RememberTokenReused` returned.
+    /// The session starts before the validator rotates, so a session that fails to start, e.g. on
+    /// a rejecting session limit, leaves the cookie as it was.
+    pub async fn redeem_remember_token(
+        &self,
+        cookie_value: &str,
+        session_duration: Expiry,
+    ) -> Result<(Session, String)> {
+        let (selector, validator) = RememberToken::parse_cookie(cookie_value)
+            .ok_or_else(|| anyhow::anyhow!("Malformed remember token"))?;
+        let mut token = self.gateway.get_remember_token(selector).await?;
+
+        if token.is_expired() {
+            self.gateway.delete_remember_token(selector).await?;
+            return Err(anyhow::anyhow!("Remember token expired"));
+        }
+
+        if !token.match_validator(validator) {
+            self.gateway.delete_remember_token(selector).await?;
+            return Err(SessionError::RememberTokenReused.into());
+        }
+
+        let session = self.start_session(&token.user_id, session_duration).await?;
+        let old_hashed_validator = token.hashed_validator.clone();
+        let rotated_cookie_value = token.rotate();
+
+        // A lost compare and swap means another request already used this validator
+        if !self
+            .gateway
+            .rotate_remember_token(selector, &old_hashed_validator, &token.hashed_validator)
+            .await?
+        {
+            self.gateway.delete_session(&session.session_id).await?;
+            self.gateway.delete_remember_token(selector).await?;
+            return Err(SessionError::RememberTokenReused.into());
+        }
+
+        Ok((session, rotated_cookie_value))
+    }
+
+    /// Ends the remember-me series for the cookie, e.g. on logout
+    pub async fn forget_remember_token(&self, cookie_value: &str) -> Result<()> {
+        match RememberToken::parse_cookie(cookie_value) {
+            Some((selector, _)) => self.gateway.delete_remember_token(selector).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Ends every remember-me series for the user, e.g. after a password change
+    pub async fn forget_user(&self, user_id: &str) -> Result<()> {
+        self.gateway
+            .delete_remember_tokens_by_user_id(user_id)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{SessionLimit, SessionLimitPolicy};
+    use crate::helpers::mysql_configs;
+    use crate::memory::MemoryGateway;
+
+    #[tokio::test]
+    async fn test_mysql_remember_me() {
+        let db_configs = mysql_configs();
+        let sesh = SessionClient::new_mysql_client(&db_configs.mysql_connection_string()).await;
+        let user_id = &uuid::Uuid::new_v4().to_string();
+
+        let cookie_value = sesh.remember_user(user_id, Expiry::Day(30)).await.unwrap();
+        let (session, rotated_cookie_value) = sesh
+            .redeem_remember_token(&cookie_value, Expiry::Day(1))
+            .await
+            .unwrap();
+        assert_eq!(session.user_id.as_deref(), Some(user_id.as_str()));
+        assert_ne!(rotated_cookie_value, cookie_value);
+
+        // Replaying the old cookie revokes the series, including the rotated cookie
+        let reused = sesh
+            .redeem_remember_token(&cookie_value, Expiry::Day(1))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(
+            reused.downcast_ref::<SessionError>(),
+            Some(&SessionError::RememberTokenReused)
+        );
+        assert!(sesh
+            .redeem_remember_token(&rotated_cookie_value, Expiry::Day(1))
+            .await
+            .is_err());
+
+        sesh.destroy_user_sessions(user_id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_remember_me_session_limit() {
+        let mut sesh = SessionClient::new(MemoryGateway::new());
+        sesh.session_limit = Some(SessionLimit {
+            max_sessions: 1,
+            policy: SessionLimitPolicy::Reject,
+        });
+        sesh.start_session("user-id", Expiry::Day(1)).await.unwrap();
+        let cookie_value = sesh
+            .remember_user("user-id", Expiry::Day(30))
+            .await
+            .unwrap();
+
+        // A session that fails to start leaves the cookie usable
+        let rejected = sesh
+            .redeem_remember_token(&cookie_value, Expiry::Day(1))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(
+            rejected.downcast_ref::<SessionError>(),
+            Some(&SessionError::LimitExceeded { max_sessions: 1 })
+        );
+
+        sesh.destroy_user_sessions("user-id").await.unwrap();
+        let (session, rotated_cookie_value) = sesh
+            .redeem_remember_token(&cookie_value, Expiry::Day(1))
+            .await
+            .unwrap();
+        assert_eq!(session.user_id.as_deref(), Some("user-id"));
+        assert_ne!(rotated_cookie_value, cookie_value);
+    }
+}
@@ -0,0 +1,164 @@
+use super::purge::purge_in_batches;
+use crate::config::Expiry;
+use crate::domain::entity::Claims;
+use crate::error::JwtError;
+use crate::interface::RevocationRepository;
+use crate::TokenClient;
+use anyhow::Result;
+
+impl<S: RevocationRepository> TokenClient<S> {
+    /// Validates an access token issued by this client like `validate_access_token`, also refusing
+    /// it with `JwtError::Revoked` when its `jti` was revoked, or its subject's tokens were revoked
+    /// since it was issued
+    pub async fn validate_unrevoked_access_token(&self, access_token: &str) -> Result<Claims> {
+        let claims = self.issuer.validate(access_token)?;
+
+        if self.gateway.is_token_revoked(&claims.jti).await? {
+            return Err(JwtError::Revoked.into());
+        }
+
+        match self.gateway.get_user_revoked_before(&claims.sub).await? {
+            Some(revoked_before) if claims.iat <= revoked_before => Err(JwtError::Revoked.into()),
+            _ => Ok(claims),
+        }
+    }
+
+    /// Revokes a single access token, e.g. on logout. The revocation is kept until the token expires.
+    pub async fn revoke_access_token(&self, access_token: &str) -> Result<()> {
+        let claims = self.issuer.validate(access_token)?;
+
+        self.gateway
+            .revoke_token(&claims.jti, claims.exp + self.issuer.leeway)
+            .await
+    }
+
+    /// Revokes every access token issued to the user until now, e.g. after a password change.
+    /// `iat` only has second precision, so tokens issued later in the same second are refused too.
+    /// Pair with `revoke_user_tokens` to end their refresh tokens too.
+    pub async fn revoke_user_access_tokens(&self, user_id: &str) -> Result<()> {
+        let now = Expiry::now();
+
+        self.gateway
+            .revoke_user_tokens_before(
+                user_id,
+                now,
+                now + self.issuer.lifetime + self.issuer.leeway,
+            )
+            .await
+    }
+
+    /// Deletes revocations for tokens that have expired anyway, `batch_size` rows at a time,
+    /// returning how many were deleted. Fails when `batch_size` is zero.
+    pub async fn purge_expired_revocations(&self, batch_size: u64) -> Result<u64> {
+        purge_in_batches(batch_size, || {
+            self.gateway
+                .delete_expired_revocations(Expiry::now(), batch_size)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::jwt::{JwtIssuer, JwtKey};
+    use crate::memory::MemoryGateway;
+
+    #[tokio::test]
+    async fn test_token_revocation() {
+        let issuer = JwtIssuer::new(
+            JwtKey::hs256("2024-01", b"shared-secret"),
+            "brize",
+            "mobile",
+            Expiry::Second(60),
+        );
+        let tokens = TokenClient::new(MemoryGateway::new(), issuer);
+
+        let pair = tokens.issue_tokens("user-id").await.unwrap();
+        assert!(tokens
+            .validate_unrevoked_access_token(&pair.access_token)
+            .await
+            .is_ok());
+
+        // A single revoked token is refused
+        tokens
+            .revoke_access_token(&pair.access_token)
+            .await
+            .unwrap();
+        let revoked = tokens
+            .validate_unrevoked_access_token(&pair.access_token)
+            .await
+            .unwrap_err();
+        assert_eq!(revoked.downcast_ref::<JwtError>(), Some(&JwtError::Revoked));
+
+        // Revoking the user refuses tokens issued before, but not after
+        let mut earlier = tokens.issuer.claims("user-id");
+        earlier.iat -= 10;
+        let earlier = tokens.issuer.issue_claims(&earlier).unwrap();
+        tokens.revoke_user_access_tokens("user-id").await.unwrap();
+        assert!(tokens
+            .validate_unrevoked_access_token(&earlier)
+            .await
+            .is_err());
+
+        let revoked_before = tokens
+            .gateway
+            .get_user_revoked_before("user-id")
+            .await
+            .unwrap()
+            .unwrap();
+        let mut same_second = tokens.issuer.claims("user-id");
+        same_second.iat = revoked_before;
+        let same_second = tokens.issuer.issue_claims(&same_second).unwrap();
+        assert!(tokens
+            .validate_unrevoked_access_token(&same_second)
+            .await
+            .is_err());
+
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let later = tokens.issuer.issue("user-id").unwrap();
+        assert!(tokens.validate_unrevoked_access_token(&later).await.is_ok());
+        assert!(tokens.validate_access_token(&earlier).is_ok());
+
+        // Revocations are forgotten once the tokens would have expired anyway
+        assert_eq!(tokens.purge_expired_revocations(100).await.unwrap(), 0);
+        tokens
+            .gateway
+            .revoke_token("expired-jti", Expiry::now() - 10)
+            .await
+            .unwrap();
+        assert!(tokens
+            .gateway
+            .is_token_revoked("expired-jti")
+            .await
+            .unwrap());
+        assert_eq!(tokens.purge_expired_revocations(100).await.unwrap(), 1);
+        assert!(!tokens
+            .gateway
+            .is_token_revoked("expired-jti")
+            .await
+            .unwrap());
+
+        // Each batch deletes at most batch_size revocations, across tokens and users
+        tokens
+            .gateway
+            .revoke_token("expired-jti", Expiry::now() - 10)
+            .await
+            .unwrap();
+        tokens
+            .gateway
+            .revoke_user_tokens_before("expired-user", 0, Expiry::now() - 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            tokens
+                .gateway
+                .delete_expired_revocations(Expiry::now(), 1)
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(tokens.purge_expired_revocations(1).await.unwrap(), 1);
+        assert!(tokens.purge_expired_revocations(0).await.is_err());
+    }
+}
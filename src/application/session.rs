@@ -1,12 +1,31 @@
-use crate::config::Expiry;
-use crate::domain::entity::Session;
+use super::purge::purge_in_batches;
+use crate::authorization::parse_bearer;
+use crate::config::{Expiry, RandomTokenGenerator, SessionLimit, TokenGenerator};
+use crate::domain::entity::{hash_session_token, FlashLevel, Session, SessionMetadata};
+use crate::error::SessionError;
 
 use crate::infrastructure::gateway;
 use crate::interface::SessionRepository;
 use anyhow::Result;
 
-pub struct SessionClient<S: SessionRepository> {
+/// Default number of seconds a sliding session must move before it is written back
+const DEFAULT_TOUCH_INTERVAL: u64 = 60;
+
+/// Default number of seconds a rotated session token keeps resolving for in-flight requests
+const DEFAULT_ROTATION_GRACE: u64 = 10;
+
+/// Issues and validates sessions, stored by a `SessionRepository` gateway or sealed into
+/// cookies by a `CookieStore`
+pub struct SessionClient<S> {
     pub gateway: S,
+    /// Minimum seconds the last seen time or a sliding expiration must move before `validate_session` persists it
+    pub touch_interval: u64,
+    /// Seconds a rotated token still resolves after `rotate_session`, zero deletes it immediately
+    pub rotation_grace: u64,
+    /// Optional cap on concurrent sessions per user, enforced by every `start_*` method and `upgrade_session`
+    pub session_limit: Option<SessionLimit>,
+    /// Generates the raw session tokens, 256 bit random tokens by default
+    pub token_generator: Box<dyn TokenGenerator>,
 }
 
 impl SessionClient<gateway::mysql::MySqlGateway> {
@@ -15,46 +34,227 @@ impl SessionClient<gateway::mysql::MySqlGateway> {
     ) -> SessionClient<gateway::mysql::MySqlGateway> {
         let gateway = gateway::mysql::MySqlGateway::new(database_url).await;
 
-        SessionClient { gateway }
+        SessionClient::new(gateway)
+    }
+}
+
+impl<S> SessionClient<S> {
+    pub fn new(gateway: S) -> Self {
+        Self {
+            gateway,
+            touch_interval: DEFAULT_TOUCH_INTERVAL,
+            rotation_grace: DEFAULT_ROTATION_GRACE,
+            session_limit: None,
+            token_generator: Box::new(RandomTokenGenerator::default()),
+        }
+    }
+
+    pub(crate) fn new_session(&self, duration: &Expiry, user_id: &str) -> Session {
+        Session::new(duration, user_id).with_token(self.token_generator.generate())
     }
 }
 
 impl<S: SessionRepository> SessionClient<S> {
-    /// Issues a new session token to start the user session
+    async fn insert_new_session(&self, session: &Session) -> Result<()> {
+        match &self.session_limit {
+            Some(limit) => self.gateway.insert_session_with_limit(session, limit).await,
+            None => self.gateway.insert_session(session).await,
+        }
+    }
+
+    /// Issues a new session token to start the user session.
+    /// The raw token is only available on the returned session, the repository stores its hash.
     pub async fn start_session(&self, user_id: &str, duration: Expiry) -> Result<Session> {
-        let session = Session::new(&duration, user_id);
-        self.gateway.insert_session(&session).await?;
+        let session = self.new_session(&duration, user_id);
+        self.insert_new_session(&session).await?;
         Ok(session)
     }
 
-    /// Validates the session token
-    pub async fn validate_session(&self, session_token: &str) -> Result<Session> {
+    /// Issues a new session token, recording the client it was issued to
+    pub async fn start_session_with_metadata(
+        &self,
+        user_id: &str,
+        duration: Expiry,
+        metadata: &SessionMetadata,
+    ) -> Result<Session> {
+        let session = self.new_session(&duration, user_id).with_metadata(metadata);
+        self.insert_new_session(&session).await?;
+        Ok(session)
+    }
+
+    /// Issues a session for a visitor who has not logged in yet
+    pub async fn start_guest_session(&self, duration: Expiry) -> Result<Session> {
+        let session = Session::new_guest(&duration).with_token(self.token_generator.generate());
+        self.insert_new_session(&session).await?;
+        Ok(session)
+    }
+
+    /// Issues a session that expires after being idle for `idle`, but never outlives `absolute`
+    pub async fn start_sliding_session(
+        &self,
+        user_id: &str,
+        idle: Expiry,
+        absolute: Expiry,
+    ) -> Result<Session> {
         let session = self
-            .gateway
-            .get_session_by_id(&session_token.to_string())
-            .await?;
+            .new_session(&absolute, user_id)
+            .with_idle_timeout(&idle);
+        self.insert_new_session(&session).await?;
+        Ok(session)
+    }
+
+    /// Loads the session for the token, updating when it was last seen and sliding its idle
+    /// expiration forward, while leaving queued flash messages in place
+    async fn load_session(&self, session_token: &str) -> Result<Session> {
+        let session_id = hash_session_token(session_token);
+        let mut session = self.gateway.get_session_by_id(&session_id).await?;
 
         if session.is_expired() {
-            self.gateway
-                .delete_session(&session_token.to_string())
-                .await?;
+            self.gateway.delete_session(&session_id).await?;
             Err(anyhow::anyhow!("Session expired"))
         } else {
+            if session.touch(self.touch_interval) {
+                self.gateway.touch_session(&session).await?;
+            }
+
             Ok(session)
         }
     }
 
+    /// Validates the session token, updating when it was last seen and sliding its idle expiration forward.
+    /// Queued flash messages are moved to `Session::flashes`, so they are read exactly once.
+    pub async fn validate_session(&self, session_token: &str) -> Result<Session> {
+        let mut session = self.load_session(session_token).await?;
+
+        session.flashes = session.take_flashes()?;
+        self.save_session(&mut session).await?;
+
+        Ok(session)
+    }
+
+    /// Validates the session token of an `Authorization: Bearer` header value, see `validate_session`
+    pub async fn validate_bearer(&self, header: &str) -> Result<Session> {
+        self.validate_session(parse_bearer(header)?).await
+    }
+
+    /// Persists the session data, skipping the write when nothing changed since it was loaded
+    pub async fn save_session(&self, session: &mut Session) -> Result<()> {
+        if session.data.is_modified() {
+            self.gateway.update_session_data(session).await?;
+            session.data.mark_saved();
+        }
+        Ok(())
+    }
+
+    /// Queues a flash message to be read by the next `validate_session` for this session
+    pub async fn push_flash(
+        &self,
+        session: &mut Session,
+        level: FlashLevel,
+        message: &str,
+    ) -> Result<()> {
+        session.push_flash(level, message)?;
+        self.save_session(session).await
+    }
+
+    /// Issues a new session token and CSRF token for the same user, retiring the old token.
+    /// Call this on privilege changes, such as logging in, to defend against session fixation.
+    /// A token still resolving in its grace window can not be rotated again, failing with
+    /// `SessionError::AlreadyRotated`.
+    pub async fn rotate_session(&self, session_token: &str) -> Result<Session> {
+        let session = self.load_session(session_token).await?;
+        if session.is_rotated() {
+            return Err(SessionError::AlreadyRotated.into());
+        }
+        let rotated = session.rotate().with_token(self.token_generator.generate());
+        let grace_expires_at =
+            (self.rotation_grace > 0).then(|| Expiry::now() + self.rotation_grace);
+
+        self.gateway
+            .rotate_session(&session.session_id, &rotated, grace_expires_at, None)
+            .await?;
+
+        Ok(rotated)
+    }
+
+    /// Binds a guest session to the user on login, rotating the token to prevent session fixation
+    /// while preserving the session data
+    pub async fn upgrade_session(
+        &self,
+        guest_session_token: &str,
+        user_id: &str,
+    ) -> Result<Session> {
+        let session = self.load_session(guest_session_token).await?;
+        if session.is_rotated() {
+            return Err(SessionError::AlreadyRotated.into());
+        }
+        if !session.is_guest() {
+            return Err(anyhow::anyhow!("Session already belongs to a user"));
+        }
+
+        let mut upgraded = session.rotate().with_token(self.token_generator.generate());
+        upgraded.user_id = Some(user_id.to_string());
+
+        self.gateway
+            .rotate_session(
+                &session.session_id,
+                &upgraded,
+                None,
+                self.session_limit.as_ref(),
+            )
+            .await?;
+
+        Ok(upgraded)
+    }
+
     /// Get the session details for a token
     pub async fn get_session(&mut self, session_token: &str) -> Result<Session> {
         self.gateway
-            .get_session_by_id(&session_token.to_string())
+            .get_session_by_id(&hash_session_token(session_token))
             .await
     }
 
     /// Deletes the session from the table
     pub async fn destroy_session(&mut self, session_token: &str) -> Result<()> {
         self.gateway
-            .delete_session(&session_token.to_string())
+            .delete_session(&hash_session_token(session_token))
+            .await
+    }
+
+    /// Deletes a session by its stored id, e.g. one picked from `get_user_sessions`
+    pub async fn destroy_session_by_id(&self, session_id: &str) -> Result<()> {
+        self.gateway.delete_session(&session_id.to_string()).await
+    }
+
+    /// Deletes every expired session, `batch_size` rows at a time, returning how many were deleted.
+    /// Fails when `batch_size` is zero.
+    pub async fn purge_expired_sessions(&self, batch_size: u64) -> Result<u64> {
+        purge_in_batches(batch_size, || {
+            self.gateway
+                .delete_expired_sessions(Expiry::now(), batch_size)
+        })
+        .await
+    }
+
+    /// Lists every session belonging to the user, e.g. for an "active devices" page
+    pub async fn get_user_sessions(&self, user_id: &str) -> Result<Vec<Session>> {
+        self.gateway.get_sessions_by_user_id(user_id).await
+    }
+
+    /// Deletes every session belonging to the user, logging them out everywhere
+    pub async fn destroy_user_sessions(&self, user_id: &str) -> Result<()> {
+        self.gateway.delete_sessions_by_user_id(user_id).await
+    }
+
+    /// Deletes every session belonging to the user except the one for `keep_session_token`,
+    /// e.g. after a password change
+    pub async fn destroy_other_sessions(
+        &self,
+        user_id: &str,
+        keep_session_token: &str,
+    ) -> Result<()> {
+        self.gateway
+            .delete_sessions_except(user_id, &hash_session_token(keep_session_token))
             .await
     }
 }
@@ -64,7 +264,9 @@ mod tests {
     use std::{thread::sleep, time::Duration};
 
     use super::*;
+    use crate::config::SessionLimitPolicy;
     use crate::helpers::mysql_configs;
+    use crate::memory::MemoryGateway;
 
     #[tokio::test]
     async fn test_mysql_session() {
@@ -79,10 +281,14 @@ mod tests {
             .unwrap();
 
         let is_valid = sesh
-            .validate_session(sesh_details.session_id.as_str())
+            .validate_session(sesh_details.token.as_deref().unwrap())
             .await;
         assert!(is_valid.is_ok());
 
+        let bearer = format!("Bearer {}", sesh_details.token.as_deref().unwrap());
+        assert!(sesh.validate_bearer(&bearer).await.is_ok());
+        assert!(sesh.validate_bearer("Bearer unknown").await.is_err());
+
         // Test expired session
         let user_id = &uuid::Uuid::new_v4().to_string();
         let sesh_details = sesh
@@ -92,8 +298,179 @@ mod tests {
 
         sleep(Duration::new(2, 0));
         let is_valid = sesh
-            .validate_session(sesh_details.session_id.as_str())
+            .validate_session(sesh_details.token.as_deref().unwrap())
             .await;
         assert!(is_valid.is_err());
+
+        // Test sliding session is extended on use
+        let mut sesh = sesh;
+        sesh.touch_interval = 0;
+        let sesh_details = sesh
+            .start_sliding_session(user_id, Expiry::Second(2), Expiry::Day(1))
+            .await
+            .unwrap();
+
+        sleep(Duration::new(1, 0));
+        let validated = sesh
+            .validate_session(sesh_details.token.as_deref().unwrap())
+            .await
+            .unwrap();
+        assert!(validated.expires_at > sesh_details.expires_at);
+        assert_eq!(
+            validated.absolute_expires_at,
+            sesh_details.absolute_expires_at
+        );
+
+        // Test flash messages are read exactly once
+        let mut validated = validated;
+        sesh.push_flash(&mut validated, FlashLevel::Info, "Welcome back")
+            .await
+            .unwrap();
+        let token = sesh_details.token.as_deref().unwrap();
+        let next_request = sesh.validate_session(token).await.unwrap();
+        assert_eq!(next_request.flashes.len(), 1);
+        assert_eq!(next_request.flashes[0].message, "Welcome back");
+        assert!(sesh
+            .validate_session(token)
+            .await
+            .unwrap()
+            .flashes
+            .is_empty());
+
+        // Test guest sessions upgrade on login, keeping their data
+        let mut guest = sesh.start_guest_session(Expiry::Day(1)).await.unwrap();
+        assert!(guest.is_guest());
+        guest.insert("cart", vec![1, 2]).unwrap();
+        sesh.save_session(&mut guest).await.unwrap();
+
+        let guest_token = guest.token.as_deref().unwrap();
+        let upgraded = sesh.upgrade_session(guest_token, user_id).await.unwrap();
+        assert_eq!(upgraded.user_id.as_deref(), Some(user_id.as_str()));
+        assert_ne!(upgraded.session_id, guest.session_id);
+        assert_eq!(upgraded.get::<Vec<u32>>("cart").unwrap(), Some(vec![1, 2]));
+        assert!(sesh.validate_session(guest_token).await.is_err());
+        assert!(sesh
+            .upgrade_session(upgraded.token.as_deref().unwrap(), user_id)
+            .await
+            .is_err());
+
+        // Test rotation keeps the old token alive for the grace window only
+        sesh.rotation_grace = 1;
+        let old_token = sesh_details.token.as_deref().unwrap();
+        let rotated = sesh.rotate_session(old_token).await.unwrap();
+        assert_ne!(rotated.session_id, sesh_details.session_id);
+        assert_eq!(rotated.user_id, sesh_details.user_id);
+        assert!(sesh
+            .validate_session(rotated.token.as_deref().unwrap())
+            .await
+            .is_ok());
+        assert!(sesh.validate_session(old_token).await.is_ok());
+
+        sleep(Duration::new(2, 0));
+        assert!(sesh.validate_session(old_token).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_session_rotation() {
+        let sesh = SessionClient::new(MemoryGateway::new());
+        let session = sesh.start_session("user-id", Expiry::Day(1)).await.unwrap();
+        let old_token = session.token.as_deref().unwrap();
+
+        let rotated = sesh.rotate_session(old_token).await.unwrap();
+        assert!(sesh
+            .validate_session(rotated.token.as_deref().unwrap())
+            .await
+            .is_ok());
+
+        // The old token still resolves in its grace window, but can not be rotated a second time
+        assert!(sesh.validate_session(old_token).await.is_ok());
+        let reused = sesh.rotate_session(old_token).await.err().unwrap();
+        assert_eq!(
+            reused.downcast_ref::<SessionError>(),
+            Some(&SessionError::AlreadyRotated)
+        );
+        assert_eq!(sesh.get_user_sessions("user-id").await.unwrap().len(), 2);
+
+        // Neither can a rotated guest token be upgraded
+        let guest = sesh.start_guest_session(Expiry::Day(1)).await.unwrap();
+        let guest_token = guest.token.as_deref().unwrap();
+        sesh.rotate_session(guest_token).await.unwrap();
+        assert!(sesh.upgrade_session(guest_token, "user-id").await.is_err());
+
+        // Flash messages queued before a rotation or upgrade are read through the new token
+        let mut session = sesh.start_session("user-id", Expiry::Day(1)).await.unwrap();
+        sesh.push_flash(&mut session, FlashLevel::Info, "Password changed")
+            .await
+            .unwrap();
+        let rotated = sesh
+            .rotate_session(session.token.as_deref().unwrap())
+            .await
+            .unwrap();
+        let next_request = sesh
+            .validate_session(rotated.token.as_deref().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(next_request.flashes.len(), 1);
+        assert_eq!(next_request.flashes[0].message, "Password changed");
+
+        // The old token still resolves in its grace window, without showing the flash again
+        let in_flight = sesh
+            .validate_session(session.token.as_deref().unwrap())
+            .await
+            .unwrap();
+        assert!(in_flight.is_rotated());
+        assert!(in_flight.flashes.is_empty());
+
+        let mut guest = sesh.start_guest_session(Expiry::Day(1)).await.unwrap();
+        sesh.push_flash(&mut guest, FlashLevel::Success, "Signed in")
+            .await
+            .unwrap();
+        let upgraded = sesh
+            .upgrade_session(guest.token.as_deref().unwrap(), "user-id")
+            .await
+            .unwrap();
+        let next_request = sesh
+            .validate_session(upgraded.token.as_deref().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(next_request.flashes[0].message, "Signed in");
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_session_limit() {
+        let mut sesh = SessionClient::new(MemoryGateway::new());
+        sesh.session_limit = Some(SessionLimit {
+            max_sessions: 1,
+            policy: SessionLimitPolicy::Reject,
+        });
+        let existing = sesh.start_session("user-id", Expiry::Day(1)).await.unwrap();
+
+        // A rejected upgrade leaves the guest session as it was
+        let guest = sesh.start_guest_session(Expiry::Day(1)).await.unwrap();
+        let guest_token = guest.token.as_deref().unwrap();
+        let rejected = sesh
+            .upgrade_session(guest_token, "user-id")
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(
+            rejected.downcast_ref::<SessionError>(),
+            Some(&SessionError::LimitExceeded { max_sessions: 1 })
+        );
+        assert!(sesh.validate_session(guest_token).await.unwrap().is_guest());
+
+        // Evicting makes room for the upgraded session
+        sesh.session_limit = Some(SessionLimit {
+            max_sessions: 1,
+            policy: SessionLimitPolicy::EvictOldest,
+        });
+        let upgraded = sesh.upgrade_session(guest_token, "user-id").await.unwrap();
+        let sessions = sesh.get_user_sessions("user-id").await.unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].session_id, upgraded.session_id);
+        assert!(sesh
+            .validate_session(existing.token.as_deref().unwrap())
+            .await
+            .is_err());
     }
 }
@@ -0,0 +1,255 @@
+use super::purge::purge_in_batches;
+use crate::config::{Expiry, RandomTokenGenerator, TokenGenerator};
+use crate::domain::entity::{hash_session_token, Claims, RefreshToken, TokenPair};
+use crate::domain::jwt::JwtIssuer;
+use crate::error::TokenError;
+use crate::infrastructure::gateway;
+use crate::interface::RefreshTokenRepository;
+use anyhow::Result;
+
+/// Default seconds a refresh token stays redeemable, 30 days
+const DEFAULT_REFRESH_LIFETIME: u64 = 30 * 24 * 60 * 60;
+
+/// Default seconds a refresh token family stays redeemable after login, 90 days
+const DEFAULT_FAMILY_LIFETIME: u64 = 90 * 24 * 60 * 60;
+
+/// Issues short lived JWT access tokens alongside opaque refresh tokens, stored by a
+/// `RefreshTokenRepository` gateway
+pub struct TokenClient<S> {
+    pub gateway: S,
+    /// Signs and validates the access tokens
+    pub issuer: JwtIssuer,
+    /// Seconds each refresh token stays redeemable, restarted by every refresh up to `family_lifetime`
+    pub refresh_lifetime: u64,
+    /// Seconds a refresh token family stays redeemable after login, however often it is refreshed
+    pub family_lifetime: u64,
+    /// Generates the raw refresh tokens, 256 bit random tokens by default
+    pub token_generator: Box<dyn TokenGenerator>,
+}
+
+impl TokenClient<gateway::mysql::MySqlGateway> {
+    pub async fn new_mysql_client(
+        database_url: &str,
+        issuer: JwtIssuer,
+    ) -> TokenClient<gateway::mysql::MySqlGateway> {
+        let gateway = gateway::mysql::MySqlGateway::new(database_url).await;
+
+        TokenClient::new(gateway, issuer)
+    }
+}
+
+impl<S> TokenClient<S> {
+    pub fn new(gateway: S, issuer: JwtIssuer) -> Self {
+        Self {
+            gateway,
+            issuer,
+            refresh_lifetime: DEFAULT_REFRESH_LIFETIME,
+            family_lifetime: DEFAULT_FAMILY_LIFETIME,
+            token_generator: Box::new(RandomTokenGenerator::default()),
+        }
+    }
+
+    fn issue_refresh_token(
+        &self,
+        user_id: &str,
+        family_id: String,
+        family_expires_at: u64,
+    ) -> (RefreshToken, String) {
+        RefreshToken::issue(
+            &Expiry::Second(self.refresh_lifetime),
+            user_id,
+            family_id,
+            family_expires_at,
+            self.token_generator.as_ref(),
+        )
+    }
+
+    /// Validates an access token issued by this client, see `validate_unrevoked_access_token` to
+    /// also refuse revoked tokens
+    pub fn validate_access_token(&self, access_token: &str) -> Result<Claims> {
+        Ok(self.issuer.validate(access_token)?)
+    }
+
+    fn token_pair(&self, user_id: &str, refresh_token: String) -> Result<TokenPair> {
+        Ok(TokenPair {
+            access_token: self.issuer.issue(user_id)?,
+            refresh_token,
+            expires_in: self.issuer.lifetime,
+        })
+    }
+}
+
+impl<S: RefreshTokenRepository> TokenClient<S> {
+    /// Issues an access token and starts a new refresh token family, e.g. after login
+    pub async fn issue_tokens(&self, user_id: &str) -> Result<TokenPair> {
+        let (token, raw_token) = self.issue_refresh_token(
+            user_id,
+            uuid::Uuid::new_v4().to_string(),
+            Expiry::Second(self.family_lifetime).time(),
+        );
+        self.gateway.insert_refresh_token(&token).await?;
+        self.token_pair(user_id, raw_token)
+    }
+
+    /// Exchanges a refresh token for a new access token and refresh token, invalidating the old one.
+    /// Presenting an already rotated token means it was copied, so the whole family is revoked
+    /// and `TokenError::RefreshTokenReused` returned.
+    pub async fn refresh_tokens(&self, refresh_token: &str) -> Result<TokenPair> {
+        let token = self
+            .gateway
+            .get_refresh_token(&hash_session_token(refresh_token))
+            .await?;
+
+        if token.is_expired() {
+            self.gateway
+                .delete_refresh_token_family(&token.family_id)
+                .await?;
+            return Err(anyhow::anyhow!("Refresh token expired"));
+        }
+
+        let (successor, raw_successor) = self.issue_refresh_token(
+            &token.user_id,
+            token.family_id.clone(),
+            token.family_expires_at,
+        );
+
+        // A lost compare and swap means another request already used this token
+        if token.is_rotated()
+            || !self
+                .gateway
+                .rotate_refresh_token(&token.token_id, Expiry::now(), &successor)
+                .await?
+        {
+            self.gateway
+                .delete_refresh_token_family(&token.family_id)
+                .await?;
+            return Err(TokenError::RefreshTokenReused.into());
+        }
+
+        self.token_pair(&token.user_id, raw_successor)
+    }
+
+    /// Revokes the family the refresh token belongs to, e.g. on logout
+    pub async fn revoke_refresh_token(&self, refresh_token: &str) -> Result<()> {
+        match self
+            .gateway
+            .get_refresh_token(&hash_session_token(refresh_token))
+            .await
+        {
+            Ok(token) => {
+                self.gateway
+                    .delete_refresh_token_family(&token.family_id)
+                    .await
+            }
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Revokes every refresh token for the user, e.g. after a password change
+    pub async fn revoke_user_tokens(&self, user_id: &str) -> Result<()> {
+        self.gateway.delete_refresh_tokens_by_user_id(user_id).await
+    }
+
+    /// Deletes every expired refresh token, `batch_size` rows at a time, returning how many were deleted.
+    /// Fails when `batch_size` is zero.
+    pub async fn purge_expired_refresh_tokens(&self, batch_size: u64) -> Result<u64> {
+        purge_in_batches(batch_size, || {
+            self.gateway
+                .delete_expired_refresh_tokens(Expiry::now(), batch_size)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::jwt::JwtKey;
+    use crate::helpers::mysql_configs;
+    use crate::memory::MemoryGateway;
+
+    #[tokio::test]
+    async fn test_mysql_token_client() {
+        let db_configs = mysql_configs();
+        let issuer = JwtIssuer::new(
+            JwtKey::hs256("2024-01", b"shared-secret"),
+            "brize",
+            "mobile",
+            Expiry::Second(60),
+        );
+        let tokens =
+            TokenClient::new_mysql_client(&db_configs.mysql_connection_string(), issuer).await;
+        let user_id = &uuid::Uuid::new_v4().to_string();
+
+        let pair = tokens.issue_tokens(user_id).await.unwrap();
+        assert_eq!(
+            tokens
+                .validate_access_token(&pair.access_token)
+                .unwrap()
+                .sub,
+            *user_id
+        );
+
+        let refreshed = tokens.refresh_tokens(&pair.refresh_token).await.unwrap();
+        assert_ne!(refreshed.refresh_token, pair.refresh_token);
+        assert!(tokens
+            .validate_access_token(&refreshed.access_token)
+            .is_ok());
+
+        // Replaying the rotated token revokes the family, including its successor
+        let reused = tokens
+            .refresh_tokens(&pair.refresh_token)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            reused.downcast_ref::<TokenError>(),
+            Some(&TokenError::RefreshTokenReused)
+        );
+        assert!(tokens
+            .refresh_tokens(&refreshed.refresh_token)
+            .await
+            .is_err());
+
+        // Revoking by user ends every family
+        let pair = tokens.issue_tokens(user_id).await.unwrap();
+        tokens.revoke_user_tokens(user_id).await.unwrap();
+        assert!(tokens.refresh_tokens(&pair.refresh_token).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_token_family_lifetime() {
+        let issuer = JwtIssuer::new(
+            JwtKey::hs256("2024-01", b"shared-secret"),
+            "brize",
+            "mobile",
+            Expiry::Second(60),
+        );
+        let mut tokens = TokenClient::new(MemoryGateway::new(), issuer);
+        tokens.family_lifetime = 1;
+
+        // Refreshing never carries a family past its lifetime
+        let pair = tokens.issue_tokens("user-id").await.unwrap();
+        let first = tokens
+            .gateway
+            .get_refresh_token(&hash_session_token(&pair.refresh_token))
+            .await
+            .unwrap();
+        assert_eq!(first.expires_at, first.family_expires_at);
+        let refreshed = tokens.refresh_tokens(&pair.refresh_token).await.unwrap();
+        let successor = tokens
+            .gateway
+            .get_refresh_token(&hash_session_token(&refreshed.refresh_token))
+            .await
+            .unwrap();
+        assert_eq!(successor.family_expires_at, first.family_expires_at);
+        assert_eq!(successor.expires_at, first.family_expires_at);
+
+        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+        assert!(tokens
+            .refresh_tokens(&refreshed.refresh_token)
+            .await
+            .is_err());
+
+        assert!(tokens.purge_expired_refresh_tokens(0).await.is_err());
+    }
+}
@@ -1,5 +1,10 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
+const DAY_IN_SEC: u64 = 86_400;
+const WEEK_IN_SEC: u64 = 604_800;
+const MONTH_IN_SEC: u64 = 2_678_400;
+const YEAR_IN_SEC: u64 = 31_536_000;
+
 /// A utility for creating expiration times in UNIX_EPOCH format
 pub enum Expiry {
     Second(u64),
@@ -12,12 +17,17 @@ pub enum Expiry {
 impl Expiry {
     /// Unwraps the count and converts to the UNIX_EPOCH expiration time
     pub fn time(&self) -> u64 {
+        Self::set_expiration(self.seconds())
+    }
+
+    /// Unwraps the count and converts to a duration in seconds
+    pub fn seconds(&self) -> u64 {
         match self {
-            Expiry::Second(count) => Self::set_expiration(*count),
-            Expiry::Day(count) => Self::day_expiry(*count),
-            Expiry::Week(count) => Self::week_expiry(*count),
-            Expiry::Month(count) => Self::month_expiry(*count),
-            Expiry::Year(count) => Self::year_expiry(*count),
+            Expiry::Second(count) => *count,
+            Expiry::Day(count) => DAY_IN_SEC * count,
+            Expiry::Week(count) => WEEK_IN_SEC * count,
+            Expiry::Month(count) => MONTH_IN_SEC * count,
+            Expiry::Year(count) => YEAR_IN_SEC * count,
         }
     }
 
@@ -43,26 +53,6 @@ impl Expiry {
 
         since_the_epoch.as_secs()
     }
-
-    fn day_expiry(count: u64) -> u64 {
-        let day_in_sec = 86_400;
-        Self::set_expiration(day_in_sec * count)
-    }
-
-    fn week_expiry(count: u64) -> u64 {
-        let week_in_sec = 604_800;
-        Self::set_expiration(week_in_sec * count)
-    }
-
-    fn month_expiry(count: u64) -> u64 {
-        let month_in_sec = 2_678_400;
-        Self::set_expiration(month_in_sec * count)
-    }
-
-    fn year_expiry(count: u64) -> u64 {
-        let year_in_sec = 31_536_000;
-        Self::set_expiration(year_in_sec * count)
-    }
 }
 
 #[cfg(test)]
@@ -73,5 +63,6 @@ mod tests {
     fn test_expiry_entity() {
         let exp = Expiry::Day(1);
         assert!(!exp.is_expired());
+        assert_eq!(Expiry::Week(2).seconds(), 1_209_600);
     }
 }
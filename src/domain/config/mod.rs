@@ -3,3 +3,9 @@ pub use database::*;
 
 mod expiry;
 pub use expiry::*;
+
+mod session_limit;
+pub use session_limit::*;
+
+mod token_generator;
+pub use token_generator::*;
@@ -0,0 +1,15 @@
+/// What to do when a user starts a session beyond their limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionLimitPolicy {
+    /// Delete the least recently used sessions to make room
+    EvictOldest,
+    /// Refuse the new session with `SessionError::LimitExceeded`
+    Reject,
+}
+
+/// Caps the number of concurrent sessions a single user may hold
+#[derive(Debug, Clone, Copy)]
+pub struct SessionLimit {
+    pub max_sessions: usize,
+    pub policy: SessionLimitPolicy,
+}
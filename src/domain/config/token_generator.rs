@@ -0,0 +1,60 @@
+use base64::{engine::general_purpose, Engine};
+use rand::Rng;
+
+/// Fewest random bytes a generated token may carry, 128 bits
+const MIN_ENTROPY_BYTES: usize = 16;
+
+/// Produces the raw tokens handed to clients, swap it out to inject deterministic tokens in tests
+pub trait TokenGenerator: Send + Sync {
+    fn generate(&self) -> String;
+}
+
+/// Generates base64url encoded tokens from the thread local CSPRNG
+#[derive(Debug, Clone, Copy)]
+pub struct RandomTokenGenerator {
+    entropy_bytes: usize,
+}
+
+impl RandomTokenGenerator {
+    /// Tokens carry `entropy_bytes` random bytes, never fewer than 16
+    pub fn new(entropy_bytes: usize) -> Self {
+        Self {
+            entropy_bytes: entropy_bytes.max(MIN_ENTROPY_BYTES),
+        }
+    }
+}
+
+impl Default for RandomTokenGenerator {
+    /// 256 bits of entropy, encoded as 43 characters
+    fn default() -> Self {
+        Self::new(32)
+    }
+}
+
+impl TokenGenerator for RandomTokenGenerator {
+    fn generate(&self) -> String {
+        let mut bytes = vec![0u8; self.entropy_bytes];
+        rand::rng().fill(bytes.as_mut_slice());
+
+        general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_token_generator() {
+        let generator = RandomTokenGenerator::default();
+        let token = generator.generate();
+        assert_eq!(token.len(), 43);
+        assert!(token
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_ne!(token, generator.generate());
+
+        // Entropy is clamped to 128 bits
+        assert_eq!(RandomTokenGenerator::new(4).generate().len(), 22);
+    }
+}
@@ -0,0 +1,175 @@
+use crate::domain::config::Expiry;
+use crate::domain::entity::Session;
+
+/// The prefix browsers only accept on Secure, host-only cookies with `Path=/`
+const HOST_PREFIX: &str = "__Host-";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+impl SameSite {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        }
+    }
+}
+
+/// Renders `Set-Cookie` headers for session and CSRF tokens, and finds them in `Cookie` headers
+#[derive(Debug, Clone)]
+pub struct CookieConfig {
+    pub name: String,
+    /// Prefixes the name with `__Host-`, which implies Secure, `Path=/` and no Domain
+    pub host_prefix: bool,
+    pub secure: bool,
+    pub http_only: bool,
+    pub same_site: SameSite,
+    pub path: String,
+    pub domain: Option<String>,
+}
+
+impl CookieConfig {
+    /// A `__Host-session` cookie, hidden from scripts
+    pub fn session() -> Self {
+        Self {
+            name: "session".to_string(),
+            host_prefix: true,
+            secure: true,
+            http_only: true,
+            same_site: SameSite::Lax,
+            path: "/".to_string(),
+            domain: None,
+        }
+    }
+
+    /// A `__Host-csrf` cookie, readable by scripts so they can echo it back in a header
+    pub fn csrf() -> Self {
+        Self {
+            name: "csrf".to_string(),
+            http_only: false,
+            same_site: SameSite::Strict,
+            ..Self::session()
+        }
+    }
+
+    /// The cookie name as sent to the browser, including any prefix
+    pub fn cookie_name(&self) -> String {
+        if self.host_prefix {
+            format!("{HOST_PREFIX}{}", self.name)
+        } else {
+            self.name.clone()
+        }
+    }
+
+    /// Renders a `Set-Cookie` value that expires at the UNIX_EPOCH `expires_at`
+    pub fn set_cookie(&self, value: &str, expires_at: u64) -> String {
+        self.render(value, expires_at.saturating_sub(Expiry::now()))
+    }
+
+    /// Renders the `Set-Cookie` value for a freshly issued session, None when its token is not available
+    pub fn session_cookie(&self, session: &Session) -> Option<String> {
+        session
+            .token
+            .as_deref()
+            .map(|token| self.set_cookie(token, session.expires_at))
+    }
+
+    /// Renders a `Set-Cookie` value that makes the browser delete the cookie
+    pub fn removal_cookie(&self) -> String {
+        self.render("", 0)
+    }
+
+    /// Finds this cookie's value in a `Cookie` request header
+    pub fn find<'a>(&self, cookie_header: &'a str) -> Option<&'a str> {
+        let name = self.cookie_name();
+        parse_cookie_header(cookie_header)
+            .into_iter()
+            .find(|(cookie_name, _)| *cookie_name == name)
+            .map(|(_, value)| value)
+    }
+
+    fn render(&self, value: &str, max_age: u64) -> String {
+        let mut cookie = format!("{}={value}; Max-Age={max_age}", self.cookie_name());
+
+        if self.host_prefix {
+            cookie.push_str("; Path=/");
+        } else {
+            cookie.push_str(&format!("; Path={}", self.path));
+            if let Some(domain) = &self.domain {
+                cookie.push_str(&format!("; Domain={domain}"));
+            }
+        }
+
+        // Browsers reject prefixed and SameSite=None cookies that are not Secure
+        if self.secure || self.host_prefix || self.same_site == SameSite::None {
+            cookie.push_str("; Secure");
+        }
+        if self.http_only {
+            cookie.push_str("; HttpOnly");
+        }
+        cookie.push_str(&format!("; SameSite={}", self.same_site.as_str()));
+
+        cookie
+    }
+}
+
+/// Splits a `Cookie` request header into name and value pairs, unquoting quoted values
+pub fn parse_cookie_header(cookie_header: &str) -> Vec<(&str, &str)> {
+    cookie_header
+        .split(';')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(name, value)| {
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .unwrap_or(value);
+            (name.trim(), value)
+        })
+        .filter(|(name, _)| !name.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_cookies() {
+        let config = CookieConfig::session();
+        let session = Session::new(&Expiry::Second(3600), "user-id");
+        let token = session.token.clone().unwrap();
+
+        let cookie = config.session_cookie(&session).unwrap();
+        assert!(cookie.starts_with(&format!("__Host-session={token}; Max-Age=")));
+        assert!(cookie.ends_with("; Path=/; Secure; HttpOnly; SameSite=Lax"));
+        assert_eq!(
+            config.removal_cookie(),
+            "__Host-session=; Max-Age=0; Path=/; Secure; HttpOnly; SameSite=Lax"
+        );
+
+        let custom = CookieConfig {
+            host_prefix: false,
+            secure: false,
+            domain: Some("example.com".to_string()),
+            path: "/app".to_string(),
+            ..CookieConfig::csrf()
+        };
+        assert_eq!(
+            custom.set_cookie("abc", 0),
+            "csrf=abc; Max-Age=0; Path=/app; Domain=example.com; SameSite=Strict"
+        );
+
+        let header = format!("theme=dark; __Host-session={token};csrf=\"abc\"; broken");
+        assert_eq!(config.find(&header), Some(token.as_str()));
+        assert_eq!(custom.find(&header), Some("abc"));
+        assert_eq!(CookieConfig::csrf().find(&header), None);
+        assert_eq!(parse_cookie_header(&header).len(), 3);
+    }
+}
@@ -0,0 +1,204 @@
+use crate::domain::config::Expiry;
+use crate::domain::entity::CsrfToken;
+use crate::domain::error::CsrfError;
+use crate::domain::key_ring::{IdentifiedKey, KeyRing};
+use base64::{engine::general_purpose, Engine};
+use hmac::{Hmac, Mac};
+use rand::distr::Alphanumeric;
+use rand::Rng;
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Seconds a signed token may appear to come from the future, covering clock drift between servers
+const CLOCK_SKEW: u64 = 60;
+
+/// Random bytes mixed into every signed token so two tokens are never alike
+const NONCE_BYTES: usize = 16;
+
+pub fn generate_csrf_token() -> CsrfToken {
+    // Generate a random alphanumeric string of length 32
+    let random_string: String = rand::rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect();
+
+    // Encode the random string using base64 for URL safety
+    let token = general_purpose::STANDARD.encode(random_string.as_bytes());
+    token
+}
+
+/// Checks a double submitted token, e.g. a cookie value echoed back in a header or form field
+pub fn verify_double_submit(cookie_token: &str, submitted_token: &str) -> bool {
+    !cookie_token.is_empty()
+        && bool::from(cookie_token.as_bytes().ct_eq(submitted_token.as_bytes()))
+}
+
+/// A server secret used to sign CSRF tokens, identified in each token by its key id
+pub struct CsrfKey {
+    pub key_id: String,
+    secret: Vec<u8>,
+}
+
+impl CsrfKey {
+    pub fn new(key_id: &str, secret: &[u8]) -> Self {
+        Self {
+            key_id: key_id.to_string(),
+            secret: secret.to_vec(),
+        }
+    }
+
+    fn sign(&self, payload: &str, session_identifier: &str) -> HmacSha256 {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(payload.as_bytes());
+        mac.update(b".");
+        mac.update(session_identifier.as_bytes());
+        mac
+    }
+}
+
+impl IdentifiedKey for CsrfKey {
+    fn key_id(&self) -> &str {
+        &self.key_id
+    }
+}
+
+/// Issues and verifies stateless CSRF tokens, HMAC-SHA256 signed and bound to a session identifier
+/// and the time they were issued. Tokens look like `key_id.issued_at.nonce.signature`.
+pub struct CsrfSigner {
+    /// The active key signs, it and the rotated out keys verify
+    pub keys: KeyRing<CsrfKey>,
+    /// Seconds a token stays valid after being issued
+    pub max_age: u64,
+}
+
+impl CsrfSigner {
+    pub fn new(key: CsrfKey, max_age: Expiry) -> Self {
+        Self {
+            keys: KeyRing::new(key),
+            max_age: max_age.seconds(),
+        }
+    }
+
+    /// Issues a token bound to the session identifier, such as a session id or an API client id
+    pub fn issue(&self, session_identifier: &str) -> String {
+        let key = self.keys.active_key();
+        let mut nonce = [0u8; NONCE_BYTES];
+        rand::rng().fill(&mut nonce);
+
+        let payload = format!(
+            "{}.{}.{}",
+            key.key_id,
+            Expiry::now(),
+            general_purpose::URL_SAFE_NO_PAD.encode(nonce)
+        );
+        let signature = key
+            .sign(&payload, session_identifier)
+            .finalize()
+            .into_bytes();
+
+        format!(
+            "{payload}.{}",
+            general_purpose::URL_SAFE_NO_PAD.encode(signature)
+        )
+    }
+
+    /// Verifies the signature, session binding and age of a token
+    pub fn verify(&self, token: &str, session_identifier: &str) -> Result<(), CsrfError> {
+        let (payload, signature) = token.rsplit_once('.').ok_or(CsrfError::Malformed)?;
+        let mut parts = payload.rsplitn(3, '.');
+        let (_nonce, issued_at, key_id) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(nonce), Some(issued_at), Some(key_id)) => (nonce, issued_at, key_id),
+            _ => return Err(CsrfError::Malformed),
+        };
+        let issued_at: u64 = issued_at.parse().map_err(|_| CsrfError::Malformed)?;
+        let signature = general_purpose::URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| CsrfError::Malformed)?;
+
+        let key = self.keys.find(key_id).ok_or(CsrfError::UnknownKey)?;
+        key.sign(payload, session_identifier)
+            .verify_slice(&signature)
+            .map_err(|_| CsrfError::InvalidSignature)?;
+
+        let now = Expiry::now();
+        if issued_at > now + CLOCK_SKEW || issued_at + self.max_age < now {
+            return Err(CsrfError::Expired);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::error::KeyRingError;
+
+    #[test]
+    fn test_signed_csrf_tokens() {
+        let mut signer = CsrfSigner::new(CsrfKey::new("2024-01", b"first-secret"), Expiry::Day(1));
+        let token = signer.issue("session-a");
+        assert_ne!(token, signer.issue("session-a"));
+        assert_eq!(signer.verify(&token, "session-a"), Ok(()));
+        assert_eq!(
+            signer.verify(&token, "session-b"),
+            Err(CsrfError::InvalidSignature)
+        );
+        assert_eq!(
+            signer.verify("garbage", "session-a"),
+            Err(CsrfError::Malformed)
+        );
+
+        // Tokens from the previous key still verify after rotating
+        signer
+            .keys
+            .rotate(CsrfKey::new("2024-02", b"second-secret"));
+        assert!(signer.issue("session-a").starts_with("2024-02."));
+        assert_eq!(signer.verify(&token, "session-a"), Ok(()));
+        signer.keys.retire("2024-01").unwrap();
+        assert_eq!(
+            signer.verify(&token, "session-a"),
+            Err(CsrfError::UnknownKey)
+        );
+
+        // The signing key is only retired by rotating away from it
+        let current = signer.issue("session-a");
+        assert_eq!(signer.keys.retire("2024-02"), Err(KeyRingError::ActiveKey));
+        assert_eq!(signer.verify(&current, "session-a"), Ok(()));
+        signer
+            .keys
+            .retire_and_rotate("2024-02", CsrfKey::new("2024-03", b"third-secret"))
+            .unwrap();
+        assert_eq!(
+            signer.verify(&current, "session-a"),
+            Err(CsrfError::UnknownKey)
+        );
+        assert!(signer.issue("session-a").starts_with("2024-03."));
+
+        // Tokens past their max age are rejected
+        signer.max_age = 0;
+        let mut parts: Vec<&str> = token.split('.').collect();
+        let stale = (Expiry::now() - 10).to_string();
+        parts[0] = "2024-03";
+        parts[1] = &stale;
+        let key = CsrfKey::new("2024-03", b"third-secret");
+        let payload = parts[..3].join(".");
+        let signature = key.sign(&payload, "session-a").finalize().into_bytes();
+        let stale_token = format!(
+            "{payload}.{}",
+            general_purpose::URL_SAFE_NO_PAD.encode(signature)
+        );
+        assert_eq!(
+            signer.verify(&stale_token, "session-a"),
+            Err(CsrfError::Expired)
+        );
+
+        assert!(verify_double_submit("abc", "abc"));
+        assert!(!verify_double_submit("abc", "abd"));
+        assert!(!verify_double_submit("", ""));
+    }
+}
@@ -0,0 +1,149 @@
+use super::{hash_secret, match_hashed_secret, missing_scopes, CredentialsId};
+use crate::domain::config::{Expiry, RandomTokenGenerator, TokenGenerator};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Marks raw keys as brize API keys, e.g. `bk_3f9a0c7d51e2b864_<secret>`
+pub const API_KEY_PREFIX: &str = "bk_";
+
+/// Random bytes in a key id, which is visible in the raw key and safe to look up by
+const KEY_ID_BYTES: usize = 8;
+
+/// A long lived key for service to service authentication, owned by a set of credentials.
+/// The raw key is `bk_<key_id>_<secret>` and only the SHA-256 digest of the secret is stored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+    /// Hex id, shown alongside the prefix so owners can tell their keys apart
+    pub key_id: String,
+    pub hashed_secret: String,
+    pub credentials_id: CredentialsId,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub created_at: u64,
+    /// None for keys that never expire
+    pub expires_at: Option<u64>,
+    pub last_used_at: Option<u64>,
+    pub revoked_at: Option<u64>,
+}
+
+/// What a verified API key grants, without its secret
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiKeyInfo {
+    pub key_id: String,
+    pub credentials_id: CredentialsId,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub created_at: u64,
+    pub expires_at: Option<u64>,
+    pub last_used_at: Option<u64>,
+    pub revoked_at: Option<u64>,
+}
+
+impl ApiKey {
+    /// Returns the key to store and the raw key to hand the owner, which is never shown again
+    pub fn new(
+        credentials_id: &str,
+        name: &str,
+        scopes: &[&str],
+        duration: Option<&Expiry>,
+    ) -> (Self, String) {
+        let mut key_id = [0u8; KEY_ID_BYTES];
+        rand::rng().fill(&mut key_id);
+        let key_id: String = key_id.iter().map(|byte| format!("{byte:02x}")).collect();
+        let secret = RandomTokenGenerator::default().generate();
+
+        let api_key = Self {
+            hashed_secret: hash_secret(&secret),
+            credentials_id: credentials_id.to_string(),
+            name: name.to_string(),
+            scopes: scopes.iter().map(|scope| scope.to_string()).collect(),
+            created_at: Expiry::now(),
+            expires_at: duration.map(Expiry::time),
+            last_used_at: None,
+            revoked_at: None,
+            key_id,
+        };
+        let raw_key = format!("{API_KEY_PREFIX}{}_{secret}", api_key.key_id);
+
+        (api_key, raw_key)
+    }
+
+    /// Splits a raw key into its key id and secret
+    pub fn parse(raw_key: &str) -> Option<(&str, &str)> {
+        raw_key
+            .strip_prefix(API_KEY_PREFIX)?
+            .split_once('_')
+            .filter(|(key_id, secret)| {
+                key_id.len() == KEY_ID_BYTES * 2
+                    && key_id.bytes().all(|byte| byte.is_ascii_hexdigit())
+                    && !secret.is_empty()
+            })
+    }
+
+    /// Matches the secret against the stored digest in constant time
+    pub fn match_secret(&self, secret: &str) -> bool {
+        match_hashed_secret(secret, &self.hashed_secret)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at < Expiry::now())
+    }
+
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+
+    pub fn info(&self) -> ApiKeyInfo {
+        ApiKeyInfo {
+            key_id: self.key_id.clone(),
+            credentials_id: self.credentials_id.clone(),
+            name: self.name.clone(),
+            scopes: self.scopes.clone(),
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+            last_used_at: self.last_used_at,
+            revoked_at: self.revoked_at,
+        }
+    }
+}
+
+impl ApiKeyInfo {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.missing_scopes(&[scope]).is_empty()
+    }
+
+    /// The required scopes this key was not granted, empty when it may proceed
+    pub fn missing_scopes(&self, required: &[&str]) -> Vec<String> {
+        missing_scopes(&self.scopes, required)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_key_entity() {
+        let (api_key, raw_key) = ApiKey::new("credentials-id", "billing", &["invoices:read"], None);
+        assert!(raw_key.starts_with(&format!("bk_{}_", api_key.key_id)));
+        assert!(!api_key.is_expired());
+        assert!(!api_key.is_revoked());
+
+        let (key_id, secret) = ApiKey::parse(&raw_key).unwrap();
+        assert_eq!(key_id, api_key.key_id);
+        assert!(api_key.match_secret(secret));
+        assert!(!api_key.match_secret("guessed"));
+        assert!(api_key.info().has_scope("invoices:read"));
+        assert!(!api_key.info().has_scope("invoices:write"));
+
+        assert!(ApiKey::parse("bk_nothex!!nothex!_secret").is_none());
+        assert!(ApiKey::parse(&format!("bk_{}_", api_key.key_id)).is_none());
+        assert!(ApiKey::parse("sk_0011223344556677_secret").is_none());
+
+        let (mut expiring, _) = ApiKey::new("credentials-id", "ci", &[], Some(&Expiry::Day(1)));
+        assert!(!expiring.is_expired());
+        expiring.expires_at = Some(Expiry::now() - 1);
+        assert!(expiring.is_expired());
+    }
+}
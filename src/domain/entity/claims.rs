@@ -0,0 +1,64 @@
+use super::CredentialsId;
+use crate::domain::config::Expiry;
+use anyhow::{Context, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Claim names with a field of their own, which custom claims may not shadow
+const REGISTERED_CLAIMS: [&str; 7] = ["sub", "iss", "aud", "exp", "nbf", "iat", "jti"];
+
+/// The registered claims carried by access tokens, plus any custom claims
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Claims {
+    /// The credentials the token was issued for
+    pub sub: CredentialsId,
+    pub iss: String,
+    pub aud: String,
+    pub exp: u64,
+    pub nbf: u64,
+    pub iat: u64,
+    /// Unique token id
+    pub jti: String,
+    #[serde(flatten)]
+    pub custom: Map<String, Value>,
+}
+
+impl Claims {
+    pub fn new(sub: &str, iss: &str, aud: &str, lifetime: &Expiry) -> Self {
+        let now = Expiry::now();
+
+        Self {
+            sub: sub.to_string(),
+            iss: iss.to_string(),
+            aud: aud.to_string(),
+            exp: now + lifetime.seconds(),
+            nbf: now,
+            iat: now,
+            jti: uuid::Uuid::new_v4().to_string(),
+            custom: Map::new(),
+        }
+    }
+
+    /// Sets a custom claim, refusing the registered claim names, which are set through their fields
+    pub fn insert<T: Serialize>(&mut self, key: &str, value: T) -> Result<()> {
+        if REGISTERED_CLAIMS.contains(&key) {
+            return Err(anyhow::anyhow!(
+                "Claim {key} is registered, set it through its field"
+            ));
+        }
+
+        let value = serde_json::to_value(value)
+            .with_context(|| format!("Failed to serialize claim {key}"))?;
+        self.custom.insert(key.to_string(), value);
+        Ok(())
+    }
+
+    /// Gets a custom claim, None when it is not set
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        self.custom
+            .get(key)
+            .map(|value| T::deserialize(value))
+            .transpose()
+            .with_context(|| format!("Failed to deserialize claim {key}"))
+    }
+}
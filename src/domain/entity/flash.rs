@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FlashLevel {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+/// A one-time message shown on the next request, e.g. "Settings saved"
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Flash {
+    pub level: FlashLevel,
+    pub message: String,
+}
+
+impl Flash {
+    pub fn new(level: FlashLevel, message: &str) -> Self {
+        Self {
+            level,
+            message: message.to_string(),
+        }
+    }
+}
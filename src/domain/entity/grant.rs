@@ -0,0 +1,71 @@
+use super::hash_session_token;
+use subtle::ConstantTimeEq;
+
+/// Minimum seconds between writes of a key or token's last used time
+pub(crate) const LAST_USED_INTERVAL: u64 = 60;
+
+/// Scopes are stored space separated, so they must be non-empty and free of whitespace
+pub fn is_valid_scope(scope: &str) -> bool {
+    !scope.is_empty() && !scope.chars().any(char::is_whitespace)
+}
+
+/// The first scope that can not be stored, if any
+pub(crate) fn find_invalid_scope<'a>(scopes: &[&'a str]) -> Option<&'a str> {
+    scopes.iter().copied().find(|scope| !is_valid_scope(scope))
+}
+
+/// The required scopes missing from the granted ones, empty when the grant may proceed
+pub(crate) fn missing_scopes(granted: &[String], required: &[&str]) -> Vec<String> {
+    required
+        .iter()
+        .filter(|scope| !granted.iter().any(|granted| granted == *scope))
+        .map(|scope| scope.to_string())
+        .collect()
+}
+
+/// The SHA-256 digest stored in place of a key or token secret
+pub(crate) fn hash_secret(secret: &str) -> String {
+    hash_session_token(secret)
+}
+
+/// Matches a secret against its stored digest in constant time
+pub(crate) fn match_hashed_secret(secret: &str, hashed_secret: &str) -> bool {
+    hash_secret(secret)
+        .as_bytes()
+        .ct_eq(hashed_secret.as_bytes())
+        .into()
+}
+
+/// Whether a use at `now` should be written, so busy keys and tokens are not written on every request
+pub(crate) fn is_last_use_due(last_used_at: Option<u64>, now: u64) -> bool {
+    last_used_at.is_none_or(|last_used_at| now >= last_used_at + LAST_USED_INTERVAL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grant_helpers() {
+        assert!(is_valid_scope("repo:read"));
+        assert!(!is_valid_scope(""));
+        assert!(!is_valid_scope("repo read"));
+        assert_eq!(find_invalid_scope(&["repo:read", "a\tb"]), Some("a\tb"));
+        assert_eq!(find_invalid_scope(&["repo:read"]), None);
+
+        let granted = vec!["repo:read".to_string()];
+        assert!(missing_scopes(&granted, &["repo:read"]).is_empty());
+        assert_eq!(
+            missing_scopes(&granted, &["repo:read", "admin"]),
+            vec!["admin".to_string()]
+        );
+
+        let hashed = hash_secret("secret");
+        assert!(match_hashed_secret("secret", &hashed));
+        assert!(!match_hashed_secret("guessed", &hashed));
+
+        assert!(is_last_use_due(None, 100));
+        assert!(!is_last_use_due(Some(100), 100 + LAST_USED_INTERVAL - 1));
+        assert!(is_last_use_due(Some(100), 100 + LAST_USED_INTERVAL));
+    }
+}
@@ -3,3 +3,30 @@ pub use credentials::*;
 
 mod session;
 pub use session::*;
+
+mod session_metadata;
+pub use session_metadata::*;
+
+mod session_data;
+pub use session_data::*;
+
+mod flash;
+pub use flash::*;
+
+mod remember_token;
+pub use remember_token::*;
+
+mod claims;
+pub use claims::*;
+
+mod refresh_token;
+pub use refresh_token::*;
+
+mod api_key;
+pub use api_key::*;
+
+mod grant;
+pub use grant::*;
+
+mod personal_access_token;
+pub use personal_access_token::*;
@@ -0,0 +1,173 @@
+use super::{hash_secret, missing_scopes, CredentialsId};
+use crate::domain::config::Expiry;
+use rand::{distr::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+
+/// Recognizable prefix, so secret scanners can find leaked tokens
+pub const PERSONAL_ACCESS_TOKEN_PREFIX: &str = "bpat_";
+
+/// Random base62 characters in a token, about 178 bits of entropy
+const RANDOM_LEN: usize = 30;
+/// Base62 characters of the CRC32 checksum appended to the random part
+const CHECKSUM_LEN: usize = 6;
+/// Characters of the token kept as a hint, enough to recognize it in a listing
+const HINT_LEN: usize = PERSONAL_ACCESS_TOKEN_PREFIX.len() + 4;
+
+const BASE62: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// A GitHub style personal access token, `bpat_<30 random base62><6 base62 CRC32>`.
+/// The checksum lets scanners and `verify_checksum` reject typos and fakes without a lookup,
+/// and only the SHA-256 digest of the whole token is stored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersonalAccessToken {
+    pub token_id: String,
+    pub hashed_token: String,
+    /// The start of the token, e.g. `bpat_3kQz`
+    pub token_hint: String,
+    pub credentials_id: CredentialsId,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub created_at: u64,
+    /// None for tokens that never expire
+    pub expires_at: Option<u64>,
+    pub last_used_at: Option<u64>,
+    pub revoked_at: Option<u64>,
+}
+
+/// What a personal access token grants, without its digest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PersonalAccessTokenInfo {
+    pub token_id: String,
+    pub token_hint: String,
+    pub credentials_id: CredentialsId,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub created_at: u64,
+    pub expires_at: Option<u64>,
+    pub last_used_at: Option<u64>,
+    pub revoked_at: Option<u64>,
+}
+
+impl PersonalAccessToken {
+    /// Returns the token to store and the raw token to hand the owner, which is never shown again
+    pub fn new(
+        credentials_id: &str,
+        name: &str,
+        scopes: &[&str],
+        duration: Option<&Expiry>,
+    ) -> (Self, String) {
+        let random: String = rand::rng()
+            .sample_iter(Alphanumeric)
+            .take(RANDOM_LEN)
+            .map(char::from)
+            .collect();
+        let raw_token = format!(
+            "{PERSONAL_ACCESS_TOKEN_PREFIX}{random}{}",
+            checksum(&random)
+        );
+
+        let token = Self {
+            token_id: uuid::Uuid::new_v4().to_string(),
+            hashed_token: hash_secret(&raw_token),
+            token_hint: raw_token[..HINT_LEN].to_string(),
+            credentials_id: credentials_id.to_string(),
+            name: name.to_string(),
+            scopes: scopes.iter().map(|scope| scope.to_string()).collect(),
+            created_at: Expiry::now(),
+            expires_at: duration.map(Expiry::time),
+            last_used_at: None,
+            revoked_at: None,
+        };
+
+        (token, raw_token)
+    }
+
+    /// Checks the prefix, length, alphabet and checksum, without touching storage
+    pub fn verify_checksum(raw_token: &str) -> bool {
+        let Some(body) = raw_token.strip_prefix(PERSONAL_ACCESS_TOKEN_PREFIX) else {
+            return false;
+        };
+        if body.len() != RANDOM_LEN + CHECKSUM_LEN || !body.bytes().all(|b| BASE62.contains(&b)) {
+            return false;
+        }
+
+        let (random, expected) = body.split_at(RANDOM_LEN);
+        checksum(random) == expected
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at < Expiry::now())
+    }
+
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+
+    pub fn info(&self) -> PersonalAccessTokenInfo {
+        PersonalAccessTokenInfo {
+            token_id: self.token_id.clone(),
+            token_hint: self.token_hint.clone(),
+            credentials_id: self.credentials_id.clone(),
+            name: self.name.clone(),
+            scopes: self.scopes.clone(),
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+            last_used_at: self.last_used_at,
+            revoked_at: self.revoked_at,
+        }
+    }
+}
+
+impl PersonalAccessTokenInfo {
+    /// The required scopes this token was not granted, empty when it may proceed
+    pub fn missing_scopes(&self, required: &[&str]) -> Vec<String> {
+        missing_scopes(&self.scopes, required)
+    }
+}
+
+/// CRC32 of the random part, as fixed width base62
+fn checksum(random: &str) -> String {
+    let mut crc = crc32fast::hash(random.as_bytes());
+    let mut encoded = [b'0'; CHECKSUM_LEN];
+    for digit in encoded.iter_mut().rev() {
+        *digit = BASE62[(crc % 62) as usize];
+        crc /= 62;
+    }
+    String::from_utf8(encoded.to_vec()).expect("Base62 is ASCII")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_personal_access_token_entity() {
+        let (token, raw_token) = PersonalAccessToken::new(
+            "credentials-id",
+            "deploy script",
+            &["repo:read", "repo:write"],
+            Some(&Expiry::Day(90)),
+        );
+        assert_eq!(raw_token.len(), 41);
+        assert!(raw_token.starts_with(&token.token_hint));
+        assert_eq!(token.hashed_token, hash_secret(&raw_token));
+        assert!(PersonalAccessToken::verify_checksum(&raw_token));
+
+        // A single changed character fails the checksum
+        let mut typo = raw_token.clone().into_bytes();
+        typo[10] = if typo[10] == b'a' { b'b' } else { b'a' };
+        assert!(!PersonalAccessToken::verify_checksum(
+            &String::from_utf8(typo).unwrap()
+        ));
+        assert!(!PersonalAccessToken::verify_checksum(&raw_token[1..]));
+        assert!(!PersonalAccessToken::verify_checksum("bpat_short"));
+
+        let info = token.info();
+        assert!(info.missing_scopes(&["repo:read"]).is_empty());
+        assert_eq!(
+            info.missing_scopes(&["repo:read", "admin"]),
+            vec!["admin".to_string()]
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use super::hash_session_token;
+use crate::domain::config::{Expiry, RandomTokenGenerator, TokenGenerator};
+use serde::{Deserialize, Serialize};
+
+/// An opaque, single use token redeemed for a fresh access token.
+/// Only the SHA-256 digest is stored, and every refresh issued from one login shares a family,
+/// so presenting an already rotated token can revoke every token descended from it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefreshToken {
+    /// SHA-256 hex digest of the raw token
+    pub token_id: String,
+    pub family_id: String,
+    pub user_id: String,
+    pub created_at: u64,
+    /// When this token stops being redeemable, never after `family_expires_at`
+    pub expires_at: u64,
+    /// When the whole family stops being redeemable, fixed when the family started
+    pub family_expires_at: u64,
+    /// When the token was exchanged for its successor, None while it is still current
+    pub rotated_at: Option<u64>,
+}
+
+impl RefreshToken {
+    /// Starts a new family that expires with its first token, returning the token to store and
+    /// the raw token to hand the client
+    pub fn new(duration: &Expiry, user_id: &str) -> (Self, String) {
+        Self::issue(
+            duration,
+            user_id,
+            uuid::Uuid::new_v4().to_string(),
+            duration.time(),
+            &RandomTokenGenerator::default(),
+        )
+    }
+
+    /// Issues the successor in the same family, returning it with its raw token.
+    /// The successor never outlives the family.
+    pub fn rotate(&self, duration: &Expiry) -> (Self, String) {
+        Self::issue(
+            duration,
+            &self.user_id,
+            self.family_id.clone(),
+            self.family_expires_at,
+            &RandomTokenGenerator::default(),
+        )
+    }
+
+    pub(crate) fn issue(
+        duration: &Expiry,
+        user_id: &str,
+        family_id: String,
+        family_expires_at: u64,
+        generator: &dyn TokenGenerator,
+    ) -> (Self, String) {
+        let raw_token = generator.generate();
+        let token = Self {
+            token_id: hash_session_token(&raw_token),
+            family_id,
+            user_id: user_id.to_string(),
+            created_at: Expiry::now(),
+            expires_at: duration.time().min(family_expires_at),
+            family_expires_at,
+            rotated_at: None,
+        };
+
+        (token, raw_token)
+    }
+
+    pub fn is_rotated(&self) -> bool {
+        self.rotated_at.is_some()
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at < Expiry::now()
+    }
+}
+
+/// The tokens handed to a client on login or refresh
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenPair {
+    pub access_token: String,
+    pub refresh_token: String,
+    /// Seconds until the access token expires
+    pub expires_in: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_refresh_token_entity() {
+        let (token, raw_token) = RefreshToken::new(&Expiry::Day(30), "user-id");
+        assert_eq!(token.token_id, hash_session_token(&raw_token));
+        assert!(!token.is_expired());
+        assert!(!token.is_rotated());
+
+        let (successor, successor_raw) = token.rotate(&Expiry::Day(60));
+        assert_eq!(successor.family_id, token.family_id);
+        assert_eq!(successor.expires_at, token.family_expires_at);
+        assert_eq!(successor.user_id, token.user_id);
+        assert_ne!(successor_raw, raw_token);
+        assert_ne!(
+            RefreshToken::new(&Expiry::Day(30), "user-id").0.family_id,
+            token.family_id
+        );
+    }
+}
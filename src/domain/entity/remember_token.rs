@@ -0,0 +1,87 @@
+use super::hash_session_token;
+use crate::domain::config::{Expiry, RandomTokenGenerator, TokenGenerator};
+use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
+
+/// Random bytes in a selector, which identifies the series and is safe to look up by
+const SELECTOR_BYTES: usize = 16;
+
+/// A long lived "keep me signed in" token, redeemed for fresh sessions.
+/// The cookie holds `selector:validator` and only the SHA-256 digest of the validator is stored.
+/// The validator is rotated on every use, so a replayed old validator reveals the series was stolen.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RememberToken {
+    pub selector: String,
+    pub hashed_validator: String,
+    pub user_id: String,
+    pub created_at: u64,
+    pub expires_at: u64,
+}
+
+impl RememberToken {
+    /// Returns the token to store and the cookie value to hand the client
+    pub fn new(duration: &Expiry, user_id: &str) -> (Self, String) {
+        let mut token = Self {
+            selector: RandomTokenGenerator::new(SELECTOR_BYTES).generate(),
+            hashed_validator: String::new(),
+            user_id: user_id.to_string(),
+            created_at: Expiry::now(),
+            expires_at: duration.time(),
+        };
+        let cookie_value = token.rotate();
+
+        (token, cookie_value)
+    }
+
+    /// Replaces the validator, returning the new cookie value
+    pub fn rotate(&mut self) -> String {
+        let validator = RandomTokenGenerator::default().generate();
+        self.hashed_validator = hash_session_token(&validator);
+
+        format!("{}:{validator}", self.selector)
+    }
+
+    /// Splits a cookie value into its selector and validator
+    pub fn parse_cookie(cookie_value: &str) -> Option<(&str, &str)> {
+        cookie_value
+            .split_once(':')
+            .filter(|(selector, validator)| !selector.is_empty() && !validator.is_empty())
+    }
+
+    /// Matches the validator against the stored digest in constant time
+    pub fn match_validator(&self, validator: &str) -> bool {
+        hash_session_token(validator)
+            .as_bytes()
+            .ct_eq(self.hashed_validator.as_bytes())
+            .into()
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at < Expiry::now()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remember_token_entity() {
+        let (mut token, cookie_value) = RememberToken::new(&Expiry::Day(30), "user-id");
+        assert!(!token.is_expired());
+
+        let (selector, validator) = RememberToken::parse_cookie(&cookie_value).unwrap();
+        assert_eq!(selector, token.selector);
+        assert!(token.match_validator(validator));
+        assert!(!token.match_validator("guessed"));
+
+        // The old validator stops matching once rotated
+        let old_validator = validator.to_string();
+        let rotated_cookie = token.rotate();
+        assert!(rotated_cookie.starts_with(&token.selector));
+        assert!(!token.match_validator(&old_validator));
+
+        assert!(RememberToken::parse_cookie("no-separator").is_none());
+        assert!(RememberToken::parse_cookie(":validator").is_none());
+    }
+}
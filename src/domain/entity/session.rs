@@ -1,52 +1,224 @@
-use crate::domain::config::Expiry;
+use super::{Flash, FlashLevel, SessionData, SessionMetadata};
+use crate::domain::config::{Expiry, RandomTokenGenerator, TokenGenerator};
+use crate::domain::csrf::generate_csrf_token;
+use anyhow::Result;
 use base64::{engine::general_purpose, Engine};
-use rand::distr::Alphanumeric;
 use rand::Rng;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
 
+/// The raw bearer token handed to the client, never stored
 pub type SessionToken = String;
+/// The SHA-256 hex digest of a `SessionToken`, used as the storage key
+pub type SessionId = String;
 pub type CsrfToken = String;
 
+/// Session data key holding flash messages waiting for the next request
+pub(crate) const FLASH_KEY: &str = "_flash";
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Session {
-    pub session_id: SessionToken,
+    pub session_id: SessionId,
+    /// The raw token, only present on sessions freshly issued to the caller
+    #[serde(skip)]
+    pub token: Option<SessionToken>,
     pub csrf_token: CsrfToken,
-    pub user_id: String,
+    /// None for guest sessions, which have not logged in yet
+    pub user_id: Option<String>,
     pub created_at: u64,
     pub expires_at: u64,
+    pub absolute_expires_at: u64,
+    pub idle_timeout: u64,
+    pub last_seen_at: u64,
+    /// When the token was rotated away, rotated sessions only resolve for the rest of their grace window
+    pub rotated_at: Option<u64>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub device_label: Option<String>,
+    pub data: SessionData,
+    /// Flash messages taken for the current request by `SessionClient::validate_session`
+    #[serde(skip)]
+    pub flashes: Vec<Flash>,
 }
 
 impl Session {
     pub fn new(duration: &Expiry, user_id: &str) -> Self {
+        Self::issue(duration, Some(user_id.to_string()))
+    }
+
+    /// A session for a visitor who has not logged in, see `SessionClient::upgrade_session`
+    pub fn new_guest(duration: &Expiry) -> Self {
+        Self::issue(duration, None)
+    }
+
+    fn issue(duration: &Expiry, user_id: Option<String>) -> Self {
+        let created_at = Expiry::now();
+        let expires_at = duration.time();
+        let token = RandomTokenGenerator::default().generate();
+
         Self {
-            session_id: uuid::Uuid::new_v4().to_string(),
-            created_at: Expiry::now(),
-            expires_at: duration.time(),
-            user_id: user_id.to_string(),
+            session_id: hash_session_token(&token),
+            token: Some(token),
+            created_at,
+            expires_at,
+            absolute_expires_at: expires_at,
+            idle_timeout: 0,
+            last_seen_at: created_at,
+            rotated_at: None,
+            ip_address: None,
+            user_agent: None,
+            device_label: None,
+            data: SessionData::default(),
+            flashes: Vec::new(),
+            user_id,
             csrf_token: generate_csrf_token(),
         }
     }
 
+    pub fn is_guest(&self) -> bool {
+        self.user_id.is_none()
+    }
+
+    /// Replaces the token, e.g. with one from a custom `TokenGenerator`
+    pub fn with_token(mut self, token: SessionToken) -> Self {
+        self.session_id = hash_session_token(&token);
+        self.token = Some(token);
+        self
+    }
+
+    /// Records where the session came from
+    pub fn with_metadata(mut self, metadata: &SessionMetadata) -> Self {
+        self.device_label = metadata.device_label();
+        self.ip_address = metadata.ip_address.clone();
+        self.user_agent = metadata.user_agent.clone();
+        self
+    }
+
+    /// Expires the session after being idle for `idle`, while keeping the original duration as the absolute cap
+    pub fn with_idle_timeout(mut self, idle: &Expiry) -> Self {
+        self.idle_timeout = idle.seconds();
+        self.expires_at = (self.created_at + self.idle_timeout).min(self.absolute_expires_at);
+        self
+    }
+
+    pub fn is_rotated(&self) -> bool {
+        self.rotated_at.is_some()
+    }
+
     pub fn is_expired(&self) -> bool {
         self.expires_at < Expiry::now()
     }
 
+    /// Marks the session as seen and slides the idle expiration forward, never past the absolute cap.
+    /// Returns true when either moved by at least `throttle` seconds, or the expiration reached the cap,
+    /// meaning it is worth persisting.
+    pub fn touch(&mut self, throttle: u64) -> bool {
+        let now = Expiry::now();
+        let seen = now > self.last_seen_at && now - self.last_seen_at >= throttle;
+        self.last_seen_at = now;
+
+        if self.idle_timeout == 0 {
+            return seen;
+        }
+
+        let extended = (now + self.idle_timeout).min(self.absolute_expires_at);
+        if extended <= self.expires_at {
+            return seen;
+        }
+
+        if extended - self.expires_at >= throttle || extended == self.absolute_expires_at {
+            self.expires_at = extended;
+            true
+        } else {
+            seen
+        }
+    }
+
+    /// Creates a replacement session with a fresh token and CSRF token, carrying over everything else
+    pub fn rotate(&self) -> Self {
+        let token = RandomTokenGenerator::default().generate();
+
+        Self {
+            session_id: hash_session_token(&token),
+            token: Some(token),
+            csrf_token: generate_csrf_token(),
+            rotated_at: None,
+            ..self.clone()
+        }
+    }
+
+    /// Gets a typed value from the session data, None when it is not set
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        self.data.get(key)
+    }
+
+    /// Sets a value in the session data, persisted by `SessionClient::save_session`
+    pub fn insert<T: Serialize>(&mut self, key: &str, value: T) -> Result<()> {
+        self.data.insert(key, value)
+    }
+
+    /// Queues a flash message in the session data for the next request
+    pub fn push_flash(&mut self, level: FlashLevel, message: &str) -> Result<()> {
+        let mut flashes: Vec<Flash> = self.get(FLASH_KEY)?.unwrap_or_default();
+        flashes.push(Flash::new(level, message));
+        self.insert(FLASH_KEY, flashes)
+    }
+
+    /// Removes and returns the queued flash messages
+    pub fn take_flashes(&mut self) -> Result<Vec<Flash>> {
+        let flashes = self.get(FLASH_KEY)?.unwrap_or_default();
+        self.data.remove(FLASH_KEY);
+        Ok(flashes)
+    }
+
+    /// A one-time masked form of the CSRF token. Render a fresh one into every form or response,
+    /// so the secret never repeats across compressed responses, defeating BREACH style extraction.
+    pub fn masked_csrf_token(&self) -> String {
+        let secret = self.csrf_token.as_bytes();
+        let mut masked = vec![0u8; secret.len() * 2];
+        let (pad, cipher) = masked.split_at_mut(secret.len());
+
+        rand::rng().fill(pad);
+        for ((c, p), s) in cipher.iter_mut().zip(pad.iter()).zip(secret) {
+            *c = p ^ s;
+        }
+
+        general_purpose::URL_SAFE_NO_PAD.encode(masked)
+    }
+
+    /// Matches either the raw or a masked CSRF token in constant time
     pub fn match_csrf_token(&self, csrf_token: &str) -> bool {
-        self.csrf_token == csrf_token
+        let secret = self.csrf_token.as_bytes();
+
+        if csrf_token.len() == secret.len() {
+            return csrf_token.as_bytes().ct_eq(secret).into();
+        }
+
+        match unmask_csrf_token(csrf_token, secret.len()) {
+            Some(unmasked) => unmasked.ct_eq(secret).into(),
+            None => false,
+        }
+    }
+}
+
+/// Reverses `Session::masked_csrf_token`, None when the token is not a mask of the expected length
+fn unmask_csrf_token(masked: &str, secret_len: usize) -> Option<Vec<u8>> {
+    let masked = general_purpose::URL_SAFE_NO_PAD.decode(masked).ok()?;
+    if masked.len() != secret_len * 2 {
+        return None;
     }
+
+    let (pad, cipher) = masked.split_at(secret_len);
+    Some(pad.iter().zip(cipher).map(|(p, c)| p ^ c).collect())
 }
 
-fn generate_csrf_token() -> CsrfToken {
-    // Generate a random alphanumeric string of length 32
-    let random_string: String = rand::rng()
-        .sample_iter(&Alphanumeric)
-        .take(32)
-        .map(char::from)
-        .collect();
-
-    // Encode the random string using base64 for URL safety
-    let token = general_purpose::STANDARD.encode(random_string.as_bytes());
-    token
+/// Hashes a raw session token into the id it is stored and looked up by
+pub fn hash_session_token(token: &str) -> SessionId {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
 }
 
 #[cfg(test)]
@@ -58,6 +230,75 @@ mod tests {
         let session = Session::new(&Expiry::Second(1), "user_identity@mail.com");
         assert!(!session.is_expired());
         assert_eq!(session.csrf_token.len(), 44);
-        assert_eq!(session.user_id, "user_identity@mail.com");
+        assert_eq!(session.user_id.as_deref(), Some("user_identity@mail.com"));
+        assert!(!session.is_guest());
+        assert!(Session::new_guest(&Expiry::Second(1)).is_guest());
+        assert_eq!(
+            session.session_id,
+            hash_session_token(session.token.as_ref().unwrap())
+        );
+        assert_eq!(session.session_id.len(), 64);
+        assert_eq!(session.token.as_ref().unwrap().len(), 43);
+
+        let session = session.with_token("deterministic-token".to_string());
+        assert_eq!(session.token.as_deref(), Some("deterministic-token"));
+        assert_eq!(
+            session.session_id,
+            hash_session_token("deterministic-token")
+        );
+
+        assert!(session.match_csrf_token(&session.csrf_token));
+        assert!(!session.match_csrf_token(&"x".repeat(44)));
+
+        let masked = session.masked_csrf_token();
+        assert_ne!(masked, session.masked_csrf_token());
+        assert!(session.match_csrf_token(&masked));
+        assert!(!session.match_csrf_token(&masked[1..]));
+        assert!(!session.match_csrf_token("not a token"));
+
+        let mut session = session;
+        session.insert("locale", "en-US").unwrap();
+        assert_eq!(session.get::<String>("locale").unwrap().unwrap(), "en-US");
+
+        session.push_flash(FlashLevel::Success, "Saved").unwrap();
+        session.push_flash(FlashLevel::Error, "Oops").unwrap();
+        let flashes = session.take_flashes().unwrap();
+        assert_eq!(flashes.len(), 2);
+        assert_eq!(flashes[0], Flash::new(FlashLevel::Success, "Saved"));
+        assert!(session.take_flashes().unwrap().is_empty());
+
+        let rotated = session.rotate();
+        assert_ne!(rotated.session_id, session.session_id);
+        assert_ne!(rotated.token, session.token);
+        assert_ne!(rotated.csrf_token, session.csrf_token);
+        assert_eq!(rotated.user_id, session.user_id);
+        assert_eq!(rotated.absolute_expires_at, session.absolute_expires_at);
+        assert_eq!(rotated.get::<String>("locale").unwrap().unwrap(), "en-US");
+    }
+
+    #[test]
+    fn test_session_sliding_expiry() {
+        let mut session = Session::new(&Expiry::Day(1), "user_identity@mail.com")
+            .with_idle_timeout(&Expiry::Second(60));
+        assert_eq!(session.expires_at, session.created_at + 60);
+
+        // Fresh sessions are not worth extending within the throttle window
+        assert!(!session.touch(30));
+
+        // Pretend the session has been idle for a while
+        session.expires_at -= 45;
+        assert!(session.touch(30));
+        assert!(session.expires_at >= session.created_at + 60);
+
+        // Never extends beyond the absolute cap
+        session.absolute_expires_at = session.created_at + 10;
+        session.expires_at = session.created_at;
+        assert!(session.touch(30));
+        assert_eq!(session.expires_at, session.absolute_expires_at);
+        assert!(!session.touch(30));
+
+        // Last seen is persisted once the throttle window has passed
+        session.last_seen_at -= 45;
+        assert!(session.touch(30));
     }
 }
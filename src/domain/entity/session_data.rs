@@ -0,0 +1,114 @@
+use anyhow::{Context, Result};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+/// Arbitrary key/value data carried by a session, such as cart contents or locale,
+/// tracking whether it changed since it was loaded
+#[derive(Debug, Default, Clone)]
+pub struct SessionData {
+    values: Map<String, Value>,
+    modified: bool,
+}
+
+impl SessionData {
+    /// Parses the JSON object stored alongside a session
+    pub fn from_json(json: &str) -> Result<Self> {
+        let values = serde_json::from_str(json).context("Failed to parse session data")?;
+
+        Ok(Self {
+            values,
+            modified: false,
+        })
+    }
+
+    /// Serializes the data as a JSON object for storage
+    pub fn to_json(&self) -> String {
+        Value::Object(self.values.clone()).to_string()
+    }
+
+    /// Gets the value for a key, None when it is not set
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        self.values
+            .get(key)
+            .map(|value| T::deserialize(value))
+            .transpose()
+            .with_context(|| format!("Failed to deserialize session data for {key}"))
+    }
+
+    /// Sets the value for a key, replacing any previous value
+    pub fn insert<T: Serialize>(&mut self, key: &str, value: T) -> Result<()> {
+        let value = serde_json::to_value(value)
+            .with_context(|| format!("Failed to serialize session data for {key}"))?;
+        self.values.insert(key.to_string(), value);
+        self.modified = true;
+        Ok(())
+    }
+
+    /// Removes the value for a key, returning it if it was set
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        let removed = self.values.remove(key);
+        self.modified |= removed.is_some();
+        removed
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Whether the data changed since it was loaded or last saved
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    /// Marks the data as persisted
+    pub fn mark_saved(&mut self) {
+        self.modified = false;
+    }
+}
+
+impl Serialize for SessionData {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.values.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SessionData {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Self {
+            values: Map::deserialize(deserializer)?,
+            modified: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_data() {
+        let mut data = SessionData::default();
+        assert!(!data.is_modified());
+
+        data.insert("locale", "en-US").unwrap();
+        data.insert("cart", vec![3, 5, 8]).unwrap();
+        assert!(data.is_modified());
+        assert_eq!(data.get::<String>("locale").unwrap().unwrap(), "en-US");
+        assert_eq!(
+            data.get::<Vec<u32>>("cart").unwrap().unwrap(),
+            vec![3, 5, 8]
+        );
+        assert!(data.get::<String>("missing").unwrap().is_none());
+        assert!(data.get::<u32>("locale").is_err());
+
+        let loaded = SessionData::from_json(&data.to_json()).unwrap();
+        assert!(!loaded.is_modified());
+        assert_eq!(loaded.get::<String>("locale").unwrap().unwrap(), "en-US");
+
+        data.mark_saved();
+        assert!(data.remove("missing").is_none());
+        assert!(!data.is_modified());
+        assert!(data.remove("cart").is_some());
+        assert!(data.is_modified());
+    }
+}
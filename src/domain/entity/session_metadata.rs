@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+
+/// Longest user agent kept on a session, anything beyond is truncated
+const MAX_USER_AGENT_LEN: usize = 512;
+
+/// Details about the client that started a session
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SessionMetadata {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl SessionMetadata {
+    pub fn new(ip_address: Option<&str>, user_agent: Option<&str>) -> Self {
+        Self {
+            ip_address: ip_address.map(str::to_string),
+            user_agent: user_agent.map(|ua| ua.chars().take(MAX_USER_AGENT_LEN).collect()),
+        }
+    }
+
+    /// A human readable label for the client, such as "Firefox on Linux"
+    pub fn device_label(&self) -> Option<String> {
+        self.user_agent.as_deref().map(parse_device_label)
+    }
+}
+
+/// Parses a user agent into a "browser on platform" label
+pub fn parse_device_label(user_agent: &str) -> String {
+    let browser = [
+        ("Edg/", "Edge"),
+        ("OPR/", "Opera"),
+        ("Firefox/", "Firefox"),
+        ("Chrome/", "Chrome"),
+        ("Safari/", "Safari"),
+        ("curl/", "curl"),
+    ]
+    .into_iter()
+    .find(|(needle, _)| user_agent.contains(needle))
+    .map(|(_, name)| name);
+
+    let platform = [
+        ("iPhone", "iOS"),
+        ("iPad", "iPadOS"),
+        ("Android", "Android"),
+        ("CrOS", "ChromeOS"),
+        ("Windows", "Windows"),
+        ("Mac OS X", "macOS"),
+        ("Linux", "Linux"),
+    ]
+    .into_iter()
+    .find(|(needle, _)| user_agent.contains(needle))
+    .map(|(_, name)| name);
+
+    match (browser, platform) {
+        (Some(browser), Some(platform)) => format!("{browser} on {platform}"),
+        (Some(browser), None) => browser.to_string(),
+        (None, Some(platform)) => format!("Unknown browser on {platform}"),
+        (None, None) => "Unknown device".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_device_label() {
+        let chrome_windows = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+        assert_eq!(parse_device_label(chrome_windows), "Chrome on Windows");
+
+        let safari_iphone = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1";
+        assert_eq!(parse_device_label(safari_iphone), "Safari on iOS");
+
+        let firefox_linux =
+            "Mozilla/5.0 (X11; Linux x86_64; rv:121.0) Gecko/20100101 Firefox/121.0";
+        assert_eq!(parse_device_label(firefox_linux), "Firefox on Linux");
+
+        assert_eq!(parse_device_label("curl/8.4.0"), "curl");
+        assert_eq!(parse_device_label(""), "Unknown device");
+
+        let metadata = SessionMetadata::new(Some("203.0.113.7"), Some(&"x".repeat(1000)));
+        assert_eq!(metadata.user_agent.unwrap().len(), MAX_USER_AGENT_LEN);
+    }
+}
@@ -0,0 +1,271 @@
+use std::fmt;
+
+/// Session failures callers may want to handle, recoverable with `anyhow::Error::downcast_ref`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionError {
+    /// The user already holds the maximum number of sessions
+    LimitExceeded { max_sessions: usize },
+    /// An already rotated remember-me validator was presented, so its series was revoked
+    RememberTokenReused,
+    /// The session token was already rotated, and only resolves for the rest of its grace window
+    AlreadyRotated,
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::LimitExceeded { max_sessions } => {
+                write!(f, "Session limit of {max_sessions} exceeded")
+            }
+            SessionError::RememberTokenReused => {
+                write!(f, "Remember-me token reused, the series was revoked")
+            }
+            SessionError::AlreadyRotated => write!(f, "Session token was already rotated"),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+/// Reasons a refresh token exchange was refused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenError {
+    /// An already rotated refresh token was presented, so its whole family was revoked
+    RefreshTokenReused,
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenError::RefreshTokenReused => {
+                write!(f, "Refresh token reused, the token family was revoked")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TokenError {}
+
+/// Reasons a key ring refused to retire a key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyRingError {
+    /// The key is the active one, rotate to a new key first, e.g. with `KeyRing::retire_and_rotate`
+    ActiveKey,
+    /// No key with the id is in the ring
+    UnknownKey,
+    /// The replacement key has the id of the key being retired
+    DuplicateKey,
+}
+
+impl fmt::Display for KeyRingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyRingError::ActiveKey => write!(f, "Can not retire the active key"),
+            KeyRingError::UnknownKey => write!(f, "No key with the id in the key ring"),
+            KeyRingError::DuplicateKey => {
+                write!(f, "Replacement key has the id of the key being retired")
+            }
+        }
+    }
+}
+
+impl std::error::Error for KeyRingError {}
+
+/// Reasons a signed CSRF token was refused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CsrfError {
+    /// The token is not in the `key_id.issued_at.nonce.signature` format
+    Malformed,
+    /// The token was signed by a key that is not, or is no longer, in the signer
+    UnknownKey,
+    /// The signature does not match, or the token is bound to another session
+    InvalidSignature,
+    /// The token is older than the signer's max age
+    Expired,
+}
+
+impl fmt::Display for CsrfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsrfError::Malformed => write!(f, "Malformed CSRF token"),
+            CsrfError::UnknownKey => write!(f, "CSRF token signed by an unknown key"),
+            CsrfError::InvalidSignature => write!(f, "Invalid CSRF token signature"),
+            CsrfError::Expired => write!(f, "CSRF token expired"),
+        }
+    }
+}
+
+impl std::error::Error for CsrfError {}
+
+/// Reasons an access token was refused or could not be issued
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JwtError {
+    /// The token is not a well formed JWT, or is missing required claims
+    Malformed,
+    /// The signature does not verify, or the token uses another algorithm
+    InvalidSignature,
+    /// The `kid` header names a key that is not, or is no longer, in the key ring
+    UnknownKey,
+    /// `exp` has passed, beyond the allowed clock skew
+    Expired,
+    /// `nbf` has not been reached, beyond the allowed clock skew
+    NotYetValid,
+    InvalidIssuer,
+    InvalidAudience,
+    /// A signing or verification key could not be used
+    InvalidKey,
+    /// The token, or every token for its subject issued before it, was revoked
+    Revoked,
+}
+
+impl fmt::Display for JwtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JwtError::Malformed => write!(f, "Malformed access token"),
+            JwtError::InvalidSignature => write!(f, "Invalid access token signature"),
+            JwtError::UnknownKey => write!(f, "Access token signed by an unknown key"),
+            JwtError::Expired => write!(f, "Access token expired"),
+            JwtError::NotYetValid => write!(f, "Access token not yet valid"),
+            JwtError::InvalidIssuer => write!(f, "Access token has an invalid issuer"),
+            JwtError::InvalidAudience => write!(f, "Access token has an invalid audience"),
+            JwtError::InvalidKey => write!(f, "Invalid access token key"),
+            JwtError::Revoked => write!(f, "Access token revoked"),
+        }
+    }
+}
+
+impl std::error::Error for JwtError {}
+
+/// Reasons a PASETO token was refused or could not be issued
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasetoError {
+    /// The token is not a well formed v4 token, or its claims do not parse
+    Malformed,
+    /// No key for the token's purpose matches the key id in its footer
+    UnknownKey,
+    /// The signature or authentication tag does not verify, e.g. under another implicit assertion
+    InvalidSignature,
+    /// `exp` has passed, beyond the allowed clock skew
+    Expired,
+    /// `nbf` has not been reached, beyond the allowed clock skew
+    NotYetValid,
+    InvalidIssuer,
+    InvalidAudience,
+    /// A key could not be parsed, or a verify only key was asked to sign
+    InvalidKey,
+}
+
+impl fmt::Display for PasetoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PasetoError::Malformed => write!(f, "Malformed PASETO token"),
+            PasetoError::UnknownKey => write!(f, "PASETO token for an unknown key"),
+            PasetoError::InvalidSignature => write!(f, "Invalid PASETO token signature"),
+            PasetoError::Expired => write!(f, "PASETO token expired"),
+            PasetoError::NotYetValid => write!(f, "PASETO token not yet valid"),
+            PasetoError::InvalidIssuer => write!(f, "PASETO token has an invalid issuer"),
+            PasetoError::InvalidAudience => write!(f, "PASETO token has an invalid audience"),
+            PasetoError::InvalidKey => write!(f, "Invalid PASETO key"),
+        }
+    }
+}
+
+impl std::error::Error for PasetoError {}
+
+/// Reasons an API key was refused or could not be created
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiKeyError {
+    /// The key is not in the `bk_<key_id>_<secret>` format
+    Malformed,
+    /// The key is unknown, or its secret does not match
+    Invalid,
+    Revoked,
+    Expired,
+    /// A scope is empty or contains whitespace
+    InvalidScope {
+        scope: String,
+    },
+}
+
+impl fmt::Display for ApiKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiKeyError::Malformed => write!(f, "Malformed API key"),
+            ApiKeyError::Invalid => write!(f, "Invalid API key"),
+            ApiKeyError::Revoked => write!(f, "API key revoked"),
+            ApiKeyError::Expired => write!(f, "API key expired"),
+            ApiKeyError::InvalidScope { scope } => write!(f, "Invalid API key scope: {scope:?}"),
+        }
+    }
+}
+
+impl std::error::Error for ApiKeyError {}
+
+/// Reasons a personal access token was refused or could not be created
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PersonalAccessTokenError {
+    /// The prefix, length or checksum is wrong, so the token was never issued by us
+    Malformed,
+    /// The token is well formed but unknown
+    Invalid,
+    Revoked,
+    Expired,
+    /// The token is valid but was not granted every required scope
+    MissingScopes {
+        missing: Vec<String>,
+    },
+    /// A scope is empty or contains whitespace
+    InvalidScope {
+        scope: String,
+    },
+}
+
+impl fmt::Display for PersonalAccessTokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PersonalAccessTokenError::Malformed => write!(f, "Malformed personal access token"),
+            PersonalAccessTokenError::Invalid => write!(f, "Invalid personal access token"),
+            PersonalAccessTokenError::Revoked => write!(f, "Personal access token revoked"),
+            PersonalAccessTokenError::Expired => write!(f, "Personal access token expired"),
+            PersonalAccessTokenError::MissingScopes { missing } => {
+                write!(
+                    f,
+                    "Personal access token missing scopes: {}",
+                    missing.join(", ")
+                )
+            }
+            PersonalAccessTokenError::InvalidScope { scope } => {
+                write!(f, "Invalid personal access token scope: {scope:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PersonalAccessTokenError {}
+
+/// Reasons an `Authorization` header could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthorizationError {
+    /// The header is empty, or its credentials are not valid for the scheme
+    Malformed,
+    /// Basic credentials did not decode to UTF-8
+    InvalidUtf8,
+    /// A scheme other than the one expected, or other than Basic and Bearer
+    UnsupportedScheme,
+}
+
+impl fmt::Display for AuthorizationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthorizationError::Malformed => write!(f, "Malformed Authorization header"),
+            AuthorizationError::InvalidUtf8 => {
+                write!(f, "Authorization credentials are not valid UTF-8")
+            }
+            AuthorizationError::UnsupportedScheme => {
+                write!(f, "Unsupported Authorization scheme")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AuthorizationError {}
@@ -0,0 +1,203 @@
+use crate::domain::config::Expiry;
+use crate::domain::error::KeyRingError;
+
+/// A key that carries its own id, so tokens and cookies can name the key they were made with
+pub trait IdentifiedKey {
+    fn key_id(&self) -> &str;
+}
+
+struct RingKey<K> {
+    key: K,
+    activated_at: u64,
+    retired_at: Option<u64>,
+}
+
+impl<K> RingKey<K> {
+    fn is_retained(&self, retention: Option<u64>, now: u64) -> bool {
+        match (self.retired_at, retention) {
+            (Some(retired_at), Some(retention)) => retired_at + retention >= now,
+            _ => true,
+        }
+    }
+}
+
+/// The keys tokens or cookies are made with. The newest key is active and makes new ones, while
+/// keys rotated out keep verifying or opening old ones, so a rotation never breaks those in flight.
+pub struct KeyRing<K> {
+    keys: Vec<RingKey<K>>,
+    /// Seconds a rotated out key keeps working, None keeps it until retired
+    pub retention: Option<u64>,
+    /// Seconds between rotations by `rotate_if_due`, None only rotates when asked
+    pub rotation_interval: Option<u64>,
+}
+
+impl<K: IdentifiedKey> KeyRing<K> {
+    pub fn new(key: K) -> Self {
+        Self {
+            keys: vec![RingKey {
+                key,
+                activated_at: Expiry::now(),
+                retired_at: None,
+            }],
+            retention: None,
+            rotation_interval: None,
+        }
+    }
+
+    /// The key new tokens or cookies are made with
+    pub fn active_key(&self) -> &K {
+        &self.keys[0].key
+    }
+
+    /// The key with the id, while it is active or still retained
+    pub fn find(&self, key_id: &str) -> Option<&K> {
+        self.iter().find(|key| key.key_id() == key_id)
+    }
+
+    /// The active key followed by the retained keys, newest first
+    pub fn iter(&self) -> impl Iterator<Item = &K> {
+        let (retention, now) = (self.retention, Expiry::now());
+
+        self.keys
+            .iter()
+            .filter(move |ring_key| ring_key.is_retained(retention, now))
+            .map(|ring_key| &ring_key.key)
+    }
+
+    /// Makes new tokens or cookies with `key`, retaining the previous keys for old ones
+    pub fn rotate(&mut self, key: K) {
+        let now = Expiry::now();
+        self.prune();
+
+        if let Some(previous) = self.keys.first_mut() {
+            previous.retired_at = Some(now);
+        }
+        self.keys.insert(
+            0,
+            RingKey {
+                key,
+                activated_at: now,
+                retired_at: None,
+            },
+        );
+    }
+
+    /// Rotates to the key made by `next_key` once the active key is older than
+    /// `rotation_interval`, returning true when it rotated. Call it from a timer or before issuing.
+    pub fn rotate_if_due(&mut self, next_key: impl FnOnce() -> K) -> bool {
+        match self.rotation_interval {
+            Some(interval) if self.keys[0].activated_at + interval <= Expiry::now() => {
+                self.rotate(next_key());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Stops accepting anything made by a rotated out key right away, e.g. when it was compromised.
+    /// The active key is refused, as retiring it would bring an older key back into use,
+    /// see `retire_and_rotate` for replacing it.
+    pub fn retire(&mut self, key_id: &str) -> Result<(), KeyRingError> {
+        let position = self
+            .keys
+            .iter()
+            .position(|ring_key| ring_key.key.key_id() == key_id)
+            .ok_or(KeyRingError::UnknownKey)?;
+        if position == 0 {
+            return Err(KeyRingError::ActiveKey);
+        }
+
+        self.keys.remove(position);
+        Ok(())
+    }
+
+    /// Rotates to `key` and retires the key right away, e.g. when the active key was compromised
+    pub fn retire_and_rotate(&mut self, key_id: &str, key: K) -> Result<(), KeyRingError> {
+        if key.key_id() == key_id {
+            return Err(KeyRingError::DuplicateKey);
+        }
+        if !self
+            .keys
+            .iter()
+            .any(|ring_key| ring_key.key.key_id() == key_id)
+        {
+            return Err(KeyRingError::UnknownKey);
+        }
+
+        self.rotate(key);
+        self.keys.retain(|ring_key| ring_key.key.key_id() != key_id);
+        Ok(())
+    }
+
+    /// Drops rotated out keys whose retention has passed
+    pub fn prune(&mut self) {
+        let (retention, now) = (self.retention, Expiry::now());
+
+        self.keys
+            .retain(|ring_key| ring_key.is_retained(retention, now));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestKey(&'static str);
+
+    impl IdentifiedKey for TestKey {
+        fn key_id(&self) -> &str {
+            self.0
+        }
+    }
+
+    fn key_ids(ring: &KeyRing<TestKey>) -> Vec<&str> {
+        ring.iter().map(|key| key.key_id()).collect()
+    }
+
+    #[test]
+    fn test_key_ring() {
+        let mut ring = KeyRing::new(TestKey("k1"));
+        assert_eq!(ring.active_key().key_id(), "k1");
+
+        // Rotated out keys are kept until retired when there is no retention
+        ring.rotate(TestKey("k2"));
+        ring.rotate(TestKey("k3"));
+        assert_eq!(ring.active_key().key_id(), "k3");
+        assert_eq!(key_ids(&ring), vec!["k3", "k2", "k1"]);
+        assert!(ring.find("k1").is_some());
+        ring.retire("k1").unwrap();
+        assert!(ring.find("k1").is_none());
+        assert_eq!(ring.retire("k1"), Err(KeyRingError::UnknownKey));
+
+        // The active key is only retired by rotating away from it
+        assert_eq!(ring.retire("k3"), Err(KeyRingError::ActiveKey));
+        assert_eq!(ring.active_key().key_id(), "k3");
+        assert_eq!(
+            ring.retire_and_rotate("k3", TestKey("k3")),
+            Err(KeyRingError::DuplicateKey)
+        );
+        assert_eq!(
+            ring.retire_and_rotate("k9", TestKey("k4")),
+            Err(KeyRingError::UnknownKey)
+        );
+        assert_eq!(key_ids(&ring), vec!["k3", "k2"]);
+        ring.retire_and_rotate("k3", TestKey("k4")).unwrap();
+        assert_eq!(key_ids(&ring), vec!["k4", "k2"]);
+
+        // Keys past their retention stop being found, and are dropped by the next prune
+        ring.retention = Some(60);
+        ring.keys[1].retired_at = Some(Expiry::now() - 61);
+        assert!(ring.find("k2").is_none());
+        assert_eq!(key_ids(&ring), vec!["k4"]);
+        ring.prune();
+        assert_eq!(ring.keys.len(), 1);
+
+        // Scheduled rotation only happens once due
+        assert!(!ring.rotate_if_due(|| TestKey("k5")));
+        ring.rotation_interval = Some(3600);
+        assert!(!ring.rotate_if_due(|| TestKey("k5")));
+        ring.rotation_interval = Some(0);
+        assert!(ring.rotate_if_due(|| TestKey("k5")));
+        assert_eq!(key_ids(&ring), vec!["k5", "k4"]);
+    }
+}
@@ -1,2 +1,9 @@
+pub mod authorization;
 pub mod config;
+pub mod cookie;
+pub mod csrf;
 pub mod entity;
+pub mod error;
+pub mod jwt;
+pub mod key_ring;
+pub mod paseto;
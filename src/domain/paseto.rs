@@ -0,0 +1,640 @@
+use crate::domain::config::Expiry;
+use crate::domain::entity::Claims;
+use crate::domain::error::PasetoError;
+use crate::domain::key_ring::{IdentifiedKey, KeyRing};
+use base64::{engine::general_purpose, Engine};
+use blake2::{
+    digest::{
+        consts::{U32, U56},
+        Mac,
+    },
+    Blake2bMac,
+};
+use chacha20::{
+    cipher::{KeyIvInit, StreamCipher},
+    XChaCha20,
+};
+use ed25519_dalek::{
+    pkcs8::{DecodePrivateKey, DecodePublicKey},
+    Signature, Signer, SigningKey, Verifier, VerifyingKey,
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use subtle::ConstantTimeEq;
+
+/// Default seconds of clock skew tolerated when checking `exp` and `nbf`
+const DEFAULT_LEEWAY: u64 = 30;
+
+const LOCAL_HEADER: &str = "v4.local.";
+const PUBLIC_HEADER: &str = "v4.public.";
+
+const NONCE_LEN: usize = 32;
+const TAG_LEN: usize = 32;
+const SIGNATURE_LEN: usize = 64;
+
+/// Registered claims PASETO carries as ISO 8601 strings rather than epoch seconds
+const TIME_CLAIMS: [&str; 3] = ["exp", "nbf", "iat"];
+
+enum PasetoKeyKind {
+    /// v4.local, a 256 bit symmetric key
+    Local([u8; 32]),
+    /// v4.public, an Ed25519 key pair, or only the public key for verify only keys
+    Public {
+        signing: Option<Box<SigningKey>>,
+        verifying: VerifyingKey,
+    },
+}
+
+/// A key for one PASETO v4 purpose, identified in each token's footer by its key id.
+/// Each key only ever works for its own purpose, so there is no algorithm to confuse.
+pub struct PasetoKey {
+    pub key_id: String,
+    kind: PasetoKeyKind,
+}
+
+impl PasetoKey {
+    /// v4.local, encrypting the claims with a shared key
+    pub fn local(key_id: &str, key: &[u8; 32]) -> Self {
+        Self {
+            key_id: key_id.to_string(),
+            kind: PasetoKeyKind::Local(*key),
+        }
+    }
+
+    /// v4.public, signing the claims with an Ed25519 key pair from PEM encoded PKCS#8 keys
+    pub fn public_pem(key_id: &str, private_pem: &str) -> Result<Self, PasetoError> {
+        let signing =
+            SigningKey::from_pkcs8_pem(private_pem).map_err(|_| PasetoError::InvalidKey)?;
+
+        Ok(Self {
+            key_id: key_id.to_string(),
+            kind: PasetoKeyKind::Public {
+                verifying: signing.verifying_key(),
+                signing: Some(Box::new(signing)),
+            },
+        })
+    }
+
+    /// v4.public, verifying tokens signed elsewhere with the PEM encoded public key
+    pub fn verify_only_pem(key_id: &str, public_pem: &str) -> Result<Self, PasetoError> {
+        let verifying =
+            VerifyingKey::from_public_key_pem(public_pem).map_err(|_| PasetoError::InvalidKey)?;
+
+        Ok(Self {
+            key_id: key_id.to_string(),
+            kind: PasetoKeyKind::Public {
+                signing: None,
+                verifying,
+            },
+        })
+    }
+
+    /// A fresh v4.local key
+    pub fn generate_local(key_id: &str) -> Self {
+        let mut key = [0u8; 32];
+        rand::rng().fill(&mut key);
+        Self::local(key_id, &key)
+    }
+
+    /// A fresh v4.public key pair
+    pub fn generate_public(key_id: &str) -> Self {
+        let mut secret = [0u8; 32];
+        rand::rng().fill(&mut secret);
+        let signing = SigningKey::from_bytes(&secret);
+
+        Self {
+            key_id: key_id.to_string(),
+            kind: PasetoKeyKind::Public {
+                verifying: signing.verifying_key(),
+                signing: Some(Box::new(signing)),
+            },
+        }
+    }
+
+    fn header(&self) -> &'static str {
+        match self.kind {
+            PasetoKeyKind::Local(_) => LOCAL_HEADER,
+            PasetoKeyKind::Public { .. } => PUBLIC_HEADER,
+        }
+    }
+
+    /// Encrypts or signs the message into a token
+    fn seal(&self, message: &[u8], footer: &[u8], implicit: &[u8]) -> Result<String, PasetoError> {
+        let payload = match &self.kind {
+            PasetoKeyKind::Local(key) => {
+                let mut nonce = [0u8; NONCE_LEN];
+                rand::rng().fill(&mut nonce);
+                encrypt_local(key, &nonce, message, footer, implicit)
+            }
+            PasetoKeyKind::Public { signing, .. } => {
+                let signing = signing.as_ref().ok_or(PasetoError::InvalidKey)?;
+                let signature =
+                    signing.sign(&pae(&[PUBLIC_HEADER.as_bytes(), message, footer, implicit]));
+                [message, &signature.to_bytes()].concat()
+            }
+        };
+
+        Ok(assemble(self.header(), &payload, footer))
+    }
+
+    /// Decrypts or verifies the token payload, returning the message
+    fn open(&self, payload: &[u8], footer: &[u8], implicit: &[u8]) -> Result<Vec<u8>, PasetoError> {
+        match &self.kind {
+            PasetoKeyKind::Local(key) => decrypt_local(key, payload, footer, implicit),
+            PasetoKeyKind::Public { verifying, .. } => {
+                if payload.len() < SIGNATURE_LEN {
+                    return Err(PasetoError::Malformed);
+                }
+                let (message, signature) = payload.split_at(payload.len() - SIGNATURE_LEN);
+                let signature =
+                    Signature::from_slice(signature).map_err(|_| PasetoError::Malformed)?;
+
+                verifying
+                    .verify(
+                        &pae(&[PUBLIC_HEADER.as_bytes(), message, footer, implicit]),
+                        &signature,
+                    )
+                    .map_err(|_| PasetoError::InvalidSignature)?;
+
+                Ok(message.to_vec())
+            }
+        }
+    }
+}
+
+impl IdentifiedKey for PasetoKey {
+    fn key_id(&self) -> &str {
+        &self.key_id
+    }
+}
+
+/// Pre-authentication encoding, binding every piece and its length into what is authenticated
+fn pae(pieces: &[&[u8]]) -> Vec<u8> {
+    let mut encoded = (pieces.len() as u64).to_le_bytes().to_vec();
+    for piece in pieces {
+        encoded.extend((piece.len() as u64).to_le_bytes());
+        encoded.extend(*piece);
+    }
+    encoded
+}
+
+/// Splits the key into an encryption key, a stream nonce and an authentication key for this nonce
+fn local_keys(key: &[u8; 32], nonce: &[u8; NONCE_LEN]) -> ([u8; 32], [u8; 24], [u8; 32]) {
+    let mut mac = Blake2bMac::<U56>::new_with_salt_and_personal(key, &[], &[])
+        .expect("32 byte keys are valid BLAKE2b keys");
+    mac.update(b"paseto-encryption-key");
+    mac.update(nonce);
+    let derived = mac.finalize().into_bytes();
+
+    let mut mac = Blake2bMac::<U32>::new_with_salt_and_personal(key, &[], &[])
+        .expect("32 byte keys are valid BLAKE2b keys");
+    mac.update(b"paseto-auth-key-for-aead");
+    mac.update(nonce);
+    let auth_key = mac.finalize().into_bytes();
+
+    let mut encryption_key = [0u8; 32];
+    let mut stream_nonce = [0u8; 24];
+    encryption_key.copy_from_slice(&derived[..32]);
+    stream_nonce.copy_from_slice(&derived[32..]);
+
+    (encryption_key, stream_nonce, auth_key.into())
+}
+
+fn local_tag(
+    auth_key: &[u8; 32],
+    nonce: &[u8],
+    ciphertext: &[u8],
+    footer: &[u8],
+    implicit: &[u8],
+) -> [u8; TAG_LEN] {
+    let mut mac = Blake2bMac::<U32>::new_with_salt_and_personal(auth_key, &[], &[])
+        .expect("32 byte keys are valid BLAKE2b keys");
+    mac.update(&pae(&[
+        LOCAL_HEADER.as_bytes(),
+        nonce,
+        ciphertext,
+        footer,
+        implicit,
+    ]));
+    mac.finalize().into_bytes().into()
+}
+
+fn encrypt_local(
+    key: &[u8; 32],
+    nonce: &[u8; NONCE_LEN],
+    message: &[u8],
+    footer: &[u8],
+    implicit: &[u8],
+) -> Vec<u8> {
+    let (encryption_key, stream_nonce, auth_key) = local_keys(key, nonce);
+    let mut ciphertext = message.to_vec();
+    XChaCha20::new(&encryption_key.into(), &stream_nonce.into()).apply_keystream(&mut ciphertext);
+    let tag = local_tag(&auth_key, nonce, &ciphertext, footer, implicit);
+
+    [nonce.as_slice(), &ciphertext, &tag].concat()
+}
+
+fn decrypt_local(
+    key: &[u8; 32],
+    payload: &[u8],
+    footer: &[u8],
+    implicit: &[u8],
+) -> Result<Vec<u8>, PasetoError> {
+    if payload.len() < NONCE_LEN + TAG_LEN {
+        return Err(PasetoError::Malformed);
+    }
+    let (nonce, rest) = payload.split_at(NONCE_LEN);
+    let (ciphertext, tag) = rest.split_at(rest.len() - TAG_LEN);
+    let nonce: &[u8; NONCE_LEN] = nonce.try_into().map_err(|_| PasetoError::Malformed)?;
+
+    let (encryption_key, stream_nonce, auth_key) = local_keys(key, nonce);
+    let expected_tag = local_tag(&auth_key, nonce, ciphertext, footer, implicit);
+    if !bool::from(expected_tag.ct_eq(tag)) {
+        return Err(PasetoError::InvalidSignature);
+    }
+
+    let mut message = ciphertext.to_vec();
+    XChaCha20::new(&encryption_key.into(), &stream_nonce.into()).apply_keystream(&mut message);
+    Ok(message)
+}
+
+fn assemble(header: &str, payload: &[u8], footer: &[u8]) -> String {
+    let mut token = format!(
+        "{header}{}",
+        general_purpose::URL_SAFE_NO_PAD.encode(payload)
+    );
+    if !footer.is_empty() {
+        token.push('.');
+        token.push_str(&general_purpose::URL_SAFE_NO_PAD.encode(footer));
+    }
+    token
+}
+
+#[derive(Serialize, Deserialize)]
+struct Footer {
+    kid: String,
+}
+
+/// Issues and validates PASETO v4 tokens for one issuer and audience, carrying the same
+/// `Claims` as `JwtIssuer`. The signing key's id goes in the footer.
+pub struct PasetoIssuer {
+    /// The active key issues tokens, it and the rotated out keys validate them
+    pub keys: KeyRing<PasetoKey>,
+    pub issuer: String,
+    pub audience: String,
+    /// Seconds a token stays valid
+    pub lifetime: u64,
+    /// Seconds of clock skew tolerated when checking `exp` and `nbf`
+    pub leeway: u64,
+    /// Context bound into every token without being sent, e.g. a client id.
+    /// Tokens only validate when given the same assertion they were issued with.
+    pub implicit_assertion: Vec<u8>,
+}
+
+impl PasetoIssuer {
+    pub fn new(key: PasetoKey, issuer: &str, audience: &str, lifetime: Expiry) -> Self {
+        Self {
+            keys: KeyRing::new(key),
+            issuer: issuer.to_string(),
+            audience: audience.to_string(),
+            lifetime: lifetime.seconds(),
+            leeway: DEFAULT_LEEWAY,
+            implicit_assertion: Vec::new(),
+        }
+    }
+
+    /// Claims for the subject with this issuer's `iss`, `aud` and lifetime, ready for custom claims
+    pub fn claims(&self, sub: &str) -> Claims {
+        Claims::new(
+            sub,
+            &self.issuer,
+            &self.audience,
+            &Expiry::Second(self.lifetime),
+        )
+    }
+
+    /// Issues a token for the subject
+    pub fn issue(&self, sub: &str) -> Result<String, PasetoError> {
+        self.issue_claims(&self.claims(sub))
+    }
+
+    /// Encrypts or signs the claims into a token, depending on the issuing key's purpose
+    pub fn issue_claims(&self, claims: &Claims) -> Result<String, PasetoError> {
+        let key = self.keys.active_key();
+        let message =
+            serde_json::to_vec(&encode_claims(claims)?).map_err(|_| PasetoError::Malformed)?;
+        let footer = serde_json::to_vec(&Footer {
+            kid: key.key_id.clone(),
+        })
+        .map_err(|_| PasetoError::Malformed)?;
+
+        key.seal(&message, &footer, &self.implicit_assertion)
+    }
+
+    /// Decrypts or verifies the token, then checks `exp`, `nbf`, `iss` and `aud`
+    pub fn validate(&self, token: &str) -> Result<Claims, PasetoError> {
+        let (header, rest) = if let Some(rest) = token.strip_prefix(LOCAL_HEADER) {
+            (LOCAL_HEADER, rest)
+        } else if let Some(rest) = token.strip_prefix(PUBLIC_HEADER) {
+            (PUBLIC_HEADER, rest)
+        } else {
+            return Err(PasetoError::Malformed);
+        };
+
+        let (payload, footer) = match rest.split_once('.') {
+            Some((payload, footer)) => (payload, decode_part(footer)?),
+            None => (rest, Vec::new()),
+        };
+        let payload = decode_part(payload)?;
+
+        // The footer is only trusted to pick the key, it is authenticated when the key opens the token
+        let key_id = match footer.is_empty() {
+            true => None,
+            false => Some(
+                serde_json::from_slice::<Footer>(&footer)
+                    .map_err(|_| PasetoError::Malformed)?
+                    .kid,
+            ),
+        };
+        let key = self
+            .keys
+            .iter()
+            .filter(|key| key.header() == header)
+            .find(|key| key_id.as_deref().is_none_or(|key_id| key.key_id == key_id))
+            .ok_or(PasetoError::UnknownKey)?;
+
+        let message = key.open(&payload, &footer, &self.implicit_assertion)?;
+        let claims = decode_claims(&message)?;
+        let now = Expiry::now();
+
+        if claims.exp + self.leeway < now {
+            Err(PasetoError::Expired)
+        } else if claims.nbf > now + self.leeway {
+            Err(PasetoError::NotYetValid)
+        } else if claims.iss != self.issuer {
+            Err(PasetoError::InvalidIssuer)
+        } else if claims.aud != self.audience {
+            Err(PasetoError::InvalidAudience)
+        } else {
+            Ok(claims)
+        }
+    }
+}
+
+fn decode_part(part: &str) -> Result<Vec<u8>, PasetoError> {
+    general_purpose::URL_SAFE_NO_PAD
+        .decode(part)
+        .map_err(|_| PasetoError::Malformed)
+}
+
+/// Claims as PASETO expects them, with the registered times as ISO 8601 strings
+fn encode_claims(claims: &Claims) -> Result<Map<String, Value>, PasetoError> {
+    let Value::Object(mut encoded) =
+        serde_json::to_value(claims).map_err(|_| PasetoError::Malformed)?
+    else {
+        return Err(PasetoError::Malformed);
+    };
+
+    for claim in TIME_CLAIMS {
+        if let Some(seconds) = encoded.get(claim).and_then(Value::as_u64) {
+            encoded.insert(claim.to_string(), Value::String(format_timestamp(seconds)));
+        }
+    }
+    Ok(encoded)
+}
+
+fn decode_claims(message: &[u8]) -> Result<Claims, PasetoError> {
+    let mut decoded: Map<String, Value> =
+        serde_json::from_slice(message).map_err(|_| PasetoError::Malformed)?;
+
+    for claim in TIME_CLAIMS {
+        if let Some(Value::String(timestamp)) = decoded.get(claim) {
+            let seconds = parse_timestamp(timestamp).ok_or(PasetoError::Malformed)?;
+            decoded.insert(claim.to_string(), Value::from(seconds));
+        }
+    }
+    serde_json::from_value(Value::Object(decoded)).map_err(|_| PasetoError::Malformed)
+}
+
+/// Formats epoch seconds as `YYYY-MM-DDTHH:MM:SS+00:00`
+fn format_timestamp(seconds: u64) -> String {
+    let days = (seconds / 86_400) as i64;
+    let time = seconds % 86_400;
+
+    // Civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}+00:00",
+        time / 3_600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// Parses an RFC 3339 timestamp into epoch seconds, dropping fractional seconds
+fn parse_timestamp(timestamp: &str) -> Option<u64> {
+    let number = |range: std::ops::Range<usize>| -> Option<i64> {
+        let digits = timestamp.get(range)?;
+        digits
+            .bytes()
+            .all(|byte| byte.is_ascii_digit())
+            .then(|| digits.parse().ok())?
+    };
+    let bytes = timestamp.as_bytes();
+    if bytes.len() < 20
+        || bytes[4] != b'-'
+        || bytes[7] != b'-'
+        || bytes[13] != b':'
+        || bytes[16] != b':'
+    {
+        return None;
+    }
+    if !matches!(bytes[10], b'T' | b't' | b' ') {
+        return None;
+    }
+
+    let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+    let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    let mut offset_start = 19;
+    if bytes[offset_start] == b'.' {
+        offset_start += 1;
+        while bytes.get(offset_start).is_some_and(u8::is_ascii_digit) {
+            offset_start += 1;
+        }
+    }
+    let offset = match timestamp.get(offset_start..)? {
+        "Z" | "z" => 0,
+        zone if zone.len() == 6 && zone.as_bytes()[3] == b':' => {
+            let sign = match zone.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            let zone_hours = number(offset_start + 1..offset_start + 3)?;
+            let zone_minutes = number(offset_start + 4..offset_start + 6)?;
+            sign * (zone_hours * 3_600 + zone_minutes * 60)
+        }
+        _ => return None,
+    };
+
+    // Days from civil, see http://howardhinnant.github.io/date_algorithms.html
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    let seconds = days * 86_400 + hour * 3_600 + minute * 60 + second - offset;
+    u64::try_from(seconds).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::error::KeyRingError;
+
+    fn public_key(key_id: &str, seed: &[u8; 32]) -> PasetoKey {
+        let signing = SigningKey::from_bytes(seed);
+        PasetoKey {
+            key_id: key_id.to_string(),
+            kind: PasetoKeyKind::Public {
+                verifying: signing.verifying_key(),
+                signing: Some(Box::new(signing)),
+            },
+        }
+    }
+
+    #[test]
+    fn test_paseto_vectors() {
+        // Official v4 test vectors 4-E-1 and 4-S-1
+        let key: [u8; 32] = core::array::from_fn(|i| 0x70 + i as u8);
+        let message = br#"{"data":"this is a secret message","exp":"2022-01-01T00:00:00+00:00"}"#;
+        let payload = encrypt_local(&key, &[0; NONCE_LEN], message, b"", b"");
+        assert_eq!(
+            assemble(LOCAL_HEADER, &payload, b""),
+            "v4.local.AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAQAr68PS4AXe7If_ZgesdkUMvSwscFlAl1pk5HC0e8kApeaqMfGo_7OpBnwJOAbY9V7WU6abu74MmcUE8YWAiaArVI8XJ5hOb_4v9RmDkneN0S92dx0OW4pgy7omxgf3S8c3LlQg"
+        );
+        assert_eq!(decrypt_local(&key, &payload, b"", b"").unwrap(), message);
+
+        let seed = [
+            0xb4, 0xcb, 0xfb, 0x43, 0xdf, 0x4c, 0xe2, 0x10, 0x72, 0x7d, 0x95, 0x3e, 0x4a, 0x71,
+            0x33, 0x07, 0xfa, 0x19, 0xbb, 0x7d, 0x9f, 0x85, 0x04, 0x14, 0x38, 0xd9, 0xe1, 0x1b,
+            0x94, 0x2a, 0x37, 0x74,
+        ];
+        let message = br#"{"data":"this is a signed message","exp":"2022-01-01T00:00:00+00:00"}"#;
+        assert_eq!(
+            public_key("4-S-1", &seed).seal(message, b"", b"").unwrap(),
+            "v4.public.eyJkYXRhIjoidGhpcyBpcyBhIHNpZ25lZCBtZXNzYWdlIiwiZXhwIjoiMjAyMi0wMS0wMVQwMDowMDowMCswMDowMCJ9bg_XBBzds8lTZShVlwwKSgeKpLT3yukTw6JUz3W4h_ExsQV-P0V54zemZDcAxFaSeef1QlXEFtkqxT1ciiQEDA"
+        );
+
+        assert_eq!(format_timestamp(1_640_995_200), "2022-01-01T00:00:00+00:00");
+        assert_eq!(
+            parse_timestamp("2022-01-01T00:00:00+00:00"),
+            Some(1_640_995_200)
+        );
+        assert_eq!(
+            parse_timestamp("2022-01-01T01:30:00.123+01:30"),
+            Some(1_640_995_200)
+        );
+        assert_eq!(parse_timestamp("2021-12-31T23:00:00Z"), Some(1_640_991_600));
+        assert_eq!(parse_timestamp("2022-13-01T00:00:00Z"), None);
+        assert_eq!(parse_timestamp("not a timestamp"), None);
+    }
+
+    #[test]
+    fn test_paseto_issue_and_validate() {
+        let mut local = PasetoIssuer::new(
+            PasetoKey::local("2024-01", &[7; 32]),
+            "brize",
+            "mobile",
+            Expiry::Second(60),
+        );
+        let mut claims = local.claims("credentials-id");
+        claims.insert("role", "admin").unwrap();
+        let token = local.issue_claims(&claims).unwrap();
+        assert!(token.starts_with("v4.local."));
+        assert_eq!(local.validate(&token).unwrap(), claims);
+
+        // Tampering and a different implicit assertion are refused
+        let mut tampered = token.clone().into_bytes();
+        tampered[20] = if tampered[20] == b'A' { b'B' } else { b'A' };
+        assert_eq!(
+            local.validate(&String::from_utf8(tampered).unwrap()),
+            Err(PasetoError::InvalidSignature)
+        );
+        local.implicit_assertion = b"client-1".to_vec();
+        assert_eq!(local.validate(&token), Err(PasetoError::InvalidSignature));
+        let bound = local.issue("credentials-id").unwrap();
+        assert!(local.validate(&bound).is_ok());
+        local.implicit_assertion.clear();
+
+        // Claims are checked like JWTs
+        let mut expired = local.claims("credentials-id");
+        expired.exp = Expiry::now() - 600;
+        let expired = local.issue_claims(&expired).unwrap();
+        assert_eq!(local.validate(&expired), Err(PasetoError::Expired));
+        let mut other_audience = local.claims("credentials-id");
+        other_audience.aud = "web".to_string();
+        let other_audience = local.issue_claims(&other_audience).unwrap();
+        assert_eq!(
+            local.validate(&other_audience),
+            Err(PasetoError::InvalidAudience)
+        );
+
+        // Public tokens verify with the public key alone
+        let public = PasetoIssuer::new(
+            PasetoKey::generate_public("2024-01"),
+            "brize",
+            "mobile",
+            Expiry::Second(60),
+        );
+        let token = public.issue("credentials-id").unwrap();
+        assert!(token.starts_with("v4.public."));
+        assert_eq!(public.validate(&token).unwrap().sub, "credentials-id");
+
+        // A token of one purpose never opens with a key of the other
+        assert_eq!(local.validate(&token), Err(PasetoError::UnknownKey));
+        assert_eq!(local.validate("v2.local.abc"), Err(PasetoError::Malformed));
+
+        // Rotated keys keep validating until retired
+        let old_token = local.issue("credentials-id").unwrap();
+        local.keys.rotate(PasetoKey::generate_local("2024-02"));
+        assert!(local.validate(&old_token).is_ok());
+        local.keys.retire("2024-01").unwrap();
+        assert_eq!(local.validate(&old_token), Err(PasetoError::UnknownKey));
+
+        // The issuing key is only retired by rotating away from it
+        let current = local.issue("credentials-id").unwrap();
+        assert_eq!(local.keys.retire("2024-02"), Err(KeyRingError::ActiveKey));
+        assert!(local.validate(&current).is_ok());
+        local
+            .keys
+            .retire_and_rotate("2024-02", PasetoKey::generate_local("2024-03"))
+            .unwrap();
+        assert_eq!(local.validate(&current), Err(PasetoError::UnknownKey));
+        assert!(local
+            .validate(&local.issue("credentials-id").unwrap())
+            .is_ok());
+    }
+}
@@ -0,0 +1,177 @@
+use crate::domain::entity::Session;
+use crate::domain::key_ring::{IdentifiedKey, KeyRing};
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose, Engine};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use rand::Rng;
+
+/// Browsers commonly refuse cookies larger than 4KB
+const DEFAULT_MAX_COOKIE_SIZE: usize = 4096;
+
+const NONCE_LEN: usize = 24;
+
+/// A 256 bit XChaCha20-Poly1305 key, identified in each cookie by its key id
+pub struct CookieKey {
+    pub key_id: String,
+    cipher: XChaCha20Poly1305,
+}
+
+impl CookieKey {
+    pub fn new(key_id: &str, key: &[u8; 32]) -> Self {
+        Self {
+            key_id: key_id.to_string(),
+            cipher: XChaCha20Poly1305::new(key.into()),
+        }
+    }
+}
+
+impl IdentifiedKey for CookieKey {
+    fn key_id(&self) -> &str {
+        &self.key_id
+    }
+}
+
+/// The keys sessions are sealed with, the active key encrypts and every retained key decrypts.
+/// Retire a rotated out key once the longest session has expired since rotating.
+pub type CookieKeyRing = KeyRing<CookieKey>;
+
+/// Keeps the whole session in an encrypted cookie instead of a repository.
+/// Cookies look like `key_id.base64url(nonce || ciphertext)`, with the key id authenticated too.
+pub struct CookieStore {
+    pub key_ring: CookieKeyRing,
+    /// Largest sealed cookie value allowed, sessions with more data are refused
+    pub max_cookie_size: usize,
+}
+
+impl CookieStore {
+    pub fn new(key_ring: CookieKeyRing) -> Self {
+        Self {
+            key_ring,
+            max_cookie_size: DEFAULT_MAX_COOKIE_SIZE,
+        }
+    }
+
+    /// Encrypts the session into a cookie value
+    pub fn seal(&self, session: &Session) -> Result<String> {
+        let key = self.key_ring.active_key();
+        let plaintext = serde_json::to_vec(session).context("Failed to serialize session")?;
+
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::rng().fill(&mut nonce);
+        let ciphertext = key
+            .cipher
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &plaintext,
+                    aad: key.key_id.as_bytes(),
+                },
+            )
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt session"))?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+        let cookie_value = format!(
+            "{}.{}",
+            key.key_id,
+            general_purpose::URL_SAFE_NO_PAD.encode(sealed)
+        );
+
+        if cookie_value.len() > self.max_cookie_size {
+            return Err(anyhow::anyhow!(
+                "Sealed session of {} bytes exceeds the cookie size limit of {}",
+                cookie_value.len(),
+                self.max_cookie_size
+            ));
+        }
+
+        Ok(cookie_value)
+    }
+
+    /// Decrypts and authenticates a cookie value back into its session
+    pub fn open(&self, cookie_value: &str) -> Result<Session> {
+        if cookie_value.len() > self.max_cookie_size {
+            return Err(anyhow::anyhow!("Session cookie exceeds the size limit"));
+        }
+
+        let (key_id, sealed) = cookie_value
+            .rsplit_once('.')
+            .ok_or_else(|| anyhow::anyhow!("Malformed session cookie"))?;
+        let sealed = general_purpose::URL_SAFE_NO_PAD
+            .decode(sealed)
+            .context("Malformed session cookie")?;
+        if sealed.len() < NONCE_LEN {
+            return Err(anyhow::anyhow!("Malformed session cookie"));
+        }
+
+        let key = self
+            .key_ring
+            .find(key_id)
+            .ok_or_else(|| anyhow::anyhow!("Session cookie sealed by an unknown key"))?;
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let plaintext = key
+            .cipher
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: key_id.as_bytes(),
+                },
+            )
+            .map_err(|_| anyhow::anyhow!("Session cookie failed authentication"))?;
+
+        serde_json::from_slice(&plaintext).context("Failed to deserialize session")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Expiry;
+    use crate::domain::error::KeyRingError;
+
+    #[test]
+    fn test_cookie_store() {
+        let mut store = CookieStore::new(CookieKeyRing::new(CookieKey::new("k1", &[7; 32])));
+        let mut session = Session::new(&Expiry::Day(1), "user-id");
+        session.insert("locale", "en-US").unwrap();
+
+        let sealed = store.seal(&session).unwrap();
+        assert!(sealed.starts_with("k1."));
+        let opened = store.open(&sealed).unwrap();
+        assert_eq!(opened.session_id, session.session_id);
+        assert_eq!(opened.get::<String>("locale").unwrap().unwrap(), "en-US");
+
+        // Tampering is detected
+        let mut tampered = sealed.clone().into_bytes();
+        let last = tampered.len() - 1;
+        tampered[last] = if tampered[last] == b'A' { b'B' } else { b'A' };
+        assert!(store.open(&String::from_utf8(tampered).unwrap()).is_err());
+        assert!(store.open("k1.garbage").is_err());
+
+        // Cookies sealed by a rotated key keep opening until it is retired
+        store.key_ring.rotate(CookieKey::new("k2", &[9; 32]));
+        assert!(store.seal(&session).unwrap().starts_with("k2."));
+        assert!(store.open(&sealed).is_ok());
+        store.key_ring.retire("k1").unwrap();
+        assert!(store.open(&sealed).is_err());
+
+        // The sealing key is only retired by rotating away from it
+        let current = store.seal(&session).unwrap();
+        assert_eq!(store.key_ring.retire("k2"), Err(KeyRingError::ActiveKey));
+        assert!(store.open(&current).is_ok());
+        store
+            .key_ring
+            .retire_and_rotate("k2", CookieKey::new("k3", &[11; 32]))
+            .unwrap();
+        assert!(store.open(&current).is_err());
+        assert!(store.open(&store.seal(&session).unwrap()).is_ok());
+
+        // Oversized sessions are refused
+        session.insert("blob", "x".repeat(5000)).unwrap();
+        assert!(store.seal(&session).is_err());
+    }
+}
@@ -0,0 +1,27 @@
+mod refresh_token_repo;
+mod remember_token_repo;
+mod revocation_repo;
+mod session_repo;
+use crate::domain::entity::{RefreshToken, RememberToken, Session, SessionId};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// A test and development helper keeping sessions, remember-me tokens, refresh tokens and
+/// revocations in process memory. It does not store credentials, API keys or personal access
+/// tokens, so `AuthClient` can not use it. Nothing survives a restart or is shared between processes.
+#[derive(Default, Clone)]
+pub struct MemoryGateway {
+    sessions: Arc<Mutex<HashMap<SessionId, Session>>>,
+    remember_tokens: Arc<Mutex<HashMap<String, RememberToken>>>,
+    refresh_tokens: Arc<Mutex<HashMap<String, RefreshToken>>>,
+    /// Revoked token ids, with when the revocation can be forgotten
+    revoked_tokens: Arc<Mutex<HashMap<String, u64>>>,
+    /// Per user cutoffs, with when the cutoff can be forgotten
+    revoked_users: Arc<Mutex<HashMap<String, (u64, u64)>>>,
+}
+
+impl MemoryGateway {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
@@ -0,0 +1,74 @@
+use super::MemoryGateway;
+use crate::{entity::RefreshToken, interface::RefreshTokenRepository};
+use anyhow::Result;
+
+impl RefreshTokenRepository for MemoryGateway {
+    async fn insert_refresh_token(&self, token: &RefreshToken) -> Result<()> {
+        self.refresh_tokens
+            .lock()
+            .unwrap()
+            .insert(token.token_id.clone(), token.clone());
+
+        Ok(())
+    }
+
+    async fn get_refresh_token(&self, token_id: &str) -> Result<RefreshToken> {
+        self.refresh_tokens
+            .lock()
+            .unwrap()
+            .get(token_id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Failed to get refresh token by id"))
+    }
+
+    async fn rotate_refresh_token(
+        &self,
+        token_id: &str,
+        rotated_at: u64,
+        successor: &RefreshToken,
+    ) -> Result<bool> {
+        let mut refresh_tokens = self.refresh_tokens.lock().unwrap();
+
+        match refresh_tokens.get_mut(token_id) {
+            Some(token) if token.rotated_at.is_none() => token.rotated_at = Some(rotated_at),
+            _ => return Ok(false),
+        }
+        refresh_tokens.insert(successor.token_id.clone(), successor.clone());
+
+        Ok(true)
+    }
+
+    async fn delete_refresh_token_family(&self, family_id: &str) -> Result<()> {
+        self.refresh_tokens
+            .lock()
+            .unwrap()
+            .retain(|_, token| token.family_id != family_id);
+
+        Ok(())
+    }
+
+    async fn delete_refresh_tokens_by_user_id(&self, user_id: &str) -> Result<()> {
+        self.refresh_tokens
+            .lock()
+            .unwrap()
+            .retain(|_, token| token.user_id != user_id);
+
+        Ok(())
+    }
+
+    async fn delete_expired_refresh_tokens(&self, now: u64, batch_size: u64) -> Result<u64> {
+        let mut refresh_tokens = self.refresh_tokens.lock().unwrap();
+        let expired: Vec<String> = refresh_tokens
+            .values()
+            .filter(|token| token.expires_at < now)
+            .take(batch_size as usize)
+            .map(|token| token.token_id.clone())
+            .collect();
+
+        for token_id in &expired {
+            refresh_tokens.remove(token_id);
+        }
+
+        Ok(expired.len() as u64)
+    }
+}
@@ -0,0 +1,53 @@
+use super::MemoryGateway;
+use crate::{entity::RememberToken, interface::RememberTokenRepository};
+use anyhow::Result;
+
+impl RememberTokenRepository for MemoryGateway {
+    async fn insert_remember_token(&self, token: &RememberToken) -> Result<()> {
+        self.remember_tokens
+            .lock()
+            .unwrap()
+            .insert(token.selector.clone(), token.clone());
+
+        Ok(())
+    }
+
+    async fn get_remember_token(&self, selector: &str) -> Result<RememberToken> {
+        self.remember_tokens
+            .lock()
+            .unwrap()
+            .get(selector)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Failed to get remember token by selector"))
+    }
+
+    async fn rotate_remember_token(
+        &self,
+        selector: &str,
+        hashed_validator: &str,
+        new_hashed_validator: &str,
+    ) -> Result<bool> {
+        match self.remember_tokens.lock().unwrap().get_mut(selector) {
+            Some(token) if token.hashed_validator == hashed_validator => {
+                token.hashed_validator = new_hashed_validator.to_string();
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn delete_remember_token(&self, selector: &str) -> Result<()> {
+        self.remember_tokens.lock().unwrap().remove(selector);
+
+        Ok(())
+    }
+
+    async fn delete_remember_tokens_by_user_id(&self, user_id: &str) -> Result<()> {
+        self.remember_tokens
+            .lock()
+            .unwrap()
+            .retain(|_, token| token.user_id != user_id);
+
+        Ok(())
+    }
+}
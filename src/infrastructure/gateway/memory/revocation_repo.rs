@@ -0,0 +1,77 @@
+use super::MemoryGateway;
+use crate::interface::RevocationRepository;
+use anyhow::Result;
+
+impl MemoryGateway {
+    /// Forgets up to `limit` revocations that have expired, token revocations first, as MySql does
+    fn prune_revocations(&self, now: u64, limit: usize) -> u64 {
+        let mut revoked_tokens = self.revoked_tokens.lock().unwrap();
+        let mut revoked_users = self.revoked_users.lock().unwrap();
+
+        let expired_tokens: Vec<String> = revoked_tokens
+            .iter()
+            .filter(|(_, expires_at)| **expires_at < now)
+            .map(|(jti, _)| jti.clone())
+            .take(limit)
+            .collect();
+        let expired_users: Vec<String> = revoked_users
+            .iter()
+            .filter(|(_, (_, expires_at))| *expires_at < now)
+            .map(|(user_id, _)| user_id.clone())
+            .take(limit - expired_tokens.len())
+            .collect();
+
+        for jti in &expired_tokens {
+            revoked_tokens.remove(jti);
+        }
+        for user_id in &expired_users {
+            revoked_users.remove(user_id);
+        }
+
+        (expired_tokens.len() + expired_users.len()) as u64
+    }
+}
+
+impl RevocationRepository for MemoryGateway {
+    async fn revoke_token(&self, jti: &str, expires_at: u64) -> Result<()> {
+        self.revoked_tokens
+            .lock()
+            .unwrap()
+            .insert(jti.to_string(), expires_at);
+
+        Ok(())
+    }
+
+    async fn is_token_revoked(&self, jti: &str) -> Result<bool> {
+        Ok(self.revoked_tokens.lock().unwrap().contains_key(jti))
+    }
+
+    async fn revoke_user_tokens_before(
+        &self,
+        user_id: &str,
+        issued_before: u64,
+        expires_at: u64,
+    ) -> Result<()> {
+        let mut revoked_users = self.revoked_users.lock().unwrap();
+        let cutoff = revoked_users
+            .entry(user_id.to_string())
+            .or_insert((issued_before, expires_at));
+        cutoff.0 = cutoff.0.max(issued_before);
+        cutoff.1 = cutoff.1.max(expires_at);
+
+        Ok(())
+    }
+
+    async fn get_user_revoked_before(&self, user_id: &str) -> Result<Option<u64>> {
+        Ok(self
+            .revoked_users
+            .lock()
+            .unwrap()
+            .get(user_id)
+            .map(|(issued_before, _)| *issued_before))
+    }
+
+    async fn delete_expired_revocations(&self, now: u64, batch_size: u64) -> Result<u64> {
+        Ok(self.prune_revocations(now, batch_size as usize))
+    }
+}
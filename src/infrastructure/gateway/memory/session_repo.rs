@@ -0,0 +1,209 @@
+use super::MemoryGateway;
+use crate::{
+    config::{Expiry, SessionLimit, SessionLimitPolicy},
+    entity::{Session, SessionId, FLASH_KEY},
+    error::SessionError,
+    interface::SessionRepository,
+};
+use anyhow::Result;
+use std::collections::HashMap;
+
+/// Drops what is never stored, as the MySql gateway does, so sessions read back look freshly loaded
+fn stored(session: &Session) -> Session {
+    let mut session = session.clone();
+    session.token = None;
+    session.flashes.clear();
+    session.data.mark_saved();
+    session
+}
+
+/// Makes room for another session of the user per the limit, returning false when the policy rejects it
+fn make_room(
+    sessions: &mut HashMap<SessionId, Session>,
+    user_id: &str,
+    limit: &SessionLimit,
+) -> bool {
+    let now = Expiry::now();
+    sessions.retain(|_, session| {
+        session.user_id.as_deref() != Some(user_id) || session.expires_at >= now
+    });
+
+    let mut user_sessions: Vec<&Session> = sessions
+        .values()
+        .filter(|session| session.user_id.as_deref() == Some(user_id))
+        .collect();
+    if user_sessions.len() < limit.max_sessions {
+        return true;
+    }
+    if limit.policy == SessionLimitPolicy::Reject {
+        return false;
+    }
+
+    user_sessions.sort_by_key(|session| (session.last_seen_at, session.created_at));
+    let evict_count = user_sessions.len() + 1 - limit.max_sessions.max(1);
+    let evicted: Vec<SessionId> = user_sessions
+        .iter()
+        .take(evict_count)
+        .map(|session| session.session_id.clone())
+        .collect();
+    for session_id in &evicted {
+        sessions.remove(session_id);
+    }
+
+    true
+}
+
+impl SessionRepository for MemoryGateway {
+    async fn insert_session(&self, session: &Session) -> Result<()> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .insert(session.session_id.clone(), stored(session));
+
+        Ok(())
+    }
+
+    async fn insert_session_with_limit(
+        &self,
+        session: &Session,
+        limit: &SessionLimit,
+    ) -> Result<()> {
+        let mut sessions = self.sessions.lock().unwrap();
+
+        if let Some(user_id) = session.user_id.as_deref() {
+            if !make_room(&mut sessions, user_id, limit) {
+                return Err(SessionError::LimitExceeded {
+                    max_sessions: limit.max_sessions,
+                }
+                .into());
+            }
+        }
+        sessions.insert(session.session_id.clone(), stored(session));
+
+        Ok(())
+    }
+
+    async fn get_session_by_id(&self, session_id: &SessionId) -> Result<Session> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .get(session_id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Failed to get session by id"))
+    }
+
+    async fn get_sessions_by_user_id(&self, user_id: &str) -> Result<Vec<Session>> {
+        let mut sessions: Vec<Session> = self
+            .sessions
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|session| session.user_id.as_deref() == Some(user_id))
+            .cloned()
+            .collect();
+        sessions.sort_by_key(|session| session.created_at);
+
+        Ok(sessions)
+    }
+
+    async fn touch_session(&self, session: &Session) -> Result<()> {
+        if let Some(stored) = self.sessions.lock().unwrap().get_mut(&session.session_id) {
+            stored.expires_at = session.expires_at;
+            stored.last_seen_at = session.last_seen_at;
+        }
+
+        Ok(())
+    }
+
+    async fn update_session_data(&self, session: &Session) -> Result<()> {
+        if let Some(stored) = self.sessions.lock().unwrap().get_mut(&session.session_id) {
+            stored.data = session.data.clone();
+            stored.data.mark_saved();
+        }
+
+        Ok(())
+    }
+
+    async fn rotate_session(
+        &self,
+        old_session_id: &SessionId,
+        new_session: &Session,
+        grace_expires_at: Option<u64>,
+        limit: Option<&SessionLimit>,
+    ) -> Result<()> {
+        let mut sessions = self.sessions.lock().unwrap();
+
+        let not_found =
+            || anyhow::anyhow!("Failed to rotate session, session not found or already rotated");
+        if sessions.get(old_session_id).is_none_or(Session::is_rotated) {
+            return Err(not_found());
+        }
+        if let (Some(limit), Some(user_id)) = (limit, new_session.user_id.as_deref()) {
+            if !make_room(&mut sessions, user_id, limit) {
+                return Err(SessionError::LimitExceeded {
+                    max_sessions: limit.max_sessions,
+                }
+                .into());
+            }
+        }
+
+        // Making room may have evicted the old session, when it belongs to the same user
+        let old = sessions.get_mut(old_session_id).ok_or_else(not_found)?;
+        match grace_expires_at {
+            Some(grace_expires_at) => {
+                old.expires_at = old.expires_at.min(grace_expires_at);
+                old.absolute_expires_at = old.absolute_expires_at.min(grace_expires_at);
+                old.idle_timeout = 0;
+                old.rotated_at = Some(Expiry::now());
+                // Flash messages move to the new session, so they are not shown twice
+                old.data.remove(FLASH_KEY);
+                old.data.mark_saved();
+            }
+            None => {
+                sessions.remove(old_session_id);
+            }
+        }
+        sessions.insert(new_session.session_id.clone(), stored(new_session));
+
+        Ok(())
+    }
+
+    async fn delete_session(&self, session_id: &SessionId) -> Result<()> {
+        self.sessions.lock().unwrap().remove(session_id);
+
+        Ok(())
+    }
+
+    async fn delete_expired_sessions(&self, now: u64, batch_size: u64) -> Result<u64> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let expired: Vec<SessionId> = sessions
+            .values()
+            .filter(|session| session.expires_at < now)
+            .take(batch_size as usize)
+            .map(|session| session.session_id.clone())
+            .collect();
+
+        for session_id in &expired {
+            sessions.remove(session_id);
+        }
+
+        Ok(expired.len() as u64)
+    }
+
+    async fn delete_sessions_by_user_id(&self, user_id: &str) -> Result<()> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .retain(|_, session| session.user_id.as_deref() != Some(user_id));
+
+        Ok(())
+    }
+
+    async fn delete_sessions_except(&self, user_id: &str, keep: &SessionId) -> Result<()> {
+        self.sessions.lock().unwrap().retain(|session_id, session| {
+            session.user_id.as_deref() != Some(user_id) || session_id == keep
+        });
+
+        Ok(())
+    }
+}
@@ -1 +1,3 @@
+pub mod cookie_store;
+pub mod memory;
 pub mod mysql;
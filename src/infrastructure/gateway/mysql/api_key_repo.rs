@@ -0,0 +1,175 @@
+use super::MySqlGateway;
+use crate::{entity::ApiKey, interface::ApiKeyRepository};
+use anyhow::{Context, Result};
+use sqlx::{mysql::MySqlRow, FromRow, Row};
+
+impl FromRow<'_, MySqlRow> for ApiKey {
+    fn from_row(row: &MySqlRow) -> sqlx::Result<Self> {
+        let scopes: String = row.try_get("scopes")?;
+
+        Ok(Self {
+            key_id: row.try_get("key_id")?,
+            hashed_secret: row.try_get("hashed_secret")?,
+            credentials_id: row.try_get("credentials_id")?,
+            name: row.try_get("name")?,
+            scopes: scopes.split_whitespace().map(str::to_string).collect(),
+            created_at: row.try_get("created_at")?,
+            expires_at: row.try_get("expires_at")?,
+            last_used_at: row.try_get("last_used_at")?,
+            revoked_at: row.try_get("revoked_at")?,
+        })
+    }
+}
+
+impl ApiKeyRepository for MySqlGateway {
+    async fn insert_api_key(&self, api_key: &ApiKey) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO user_api_keys (key_id, hashed_secret, credentials_id, name, scopes, created_at, expires_at, last_used_at, revoked_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?);
+            "#,
+        )
+        .bind(api_key.key_id.as_str())
+        .bind(api_key.hashed_secret.as_str())
+        .bind(api_key.credentials_id.as_str())
+        .bind(api_key.name.as_str())
+        .bind(api_key.scopes.join(" "))
+        .bind(api_key.created_at as i64)
+        .bind(api_key.expires_at.map(|expires_at| expires_at as i64))
+        .bind(api_key.last_used_at.map(|last_used_at| last_used_at as i64))
+        .bind(api_key.revoked_at.map(|revoked_at| revoked_at as i64))
+        .execute(&self.pool)
+        .await
+        .context("Failed to store api key")?;
+
+        Ok(())
+    }
+
+    async fn get_api_key(&self, key_id: &str) -> Result<Option<ApiKey>> {
+        let api_key: Option<ApiKey> = sqlx::query_as(
+            r#"
+            SELECT key_id, hashed_secret, credentials_id, name, scopes, created_at, expires_at, last_used_at, revoked_at
+            FROM user_api_keys
+            WHERE key_id = ?
+            "#,
+        )
+        .bind(key_id)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to get api key by id")?;
+
+        Ok(api_key)
+    }
+
+    async fn get_api_keys_by_credentials_id(&self, credentials_id: &str) -> Result<Vec<ApiKey>> {
+        let api_keys: Vec<ApiKey> = sqlx::query_as(
+            r#"
+            SELECT key_id, hashed_secret, credentials_id, name, scopes, created_at, expires_at, last_used_at, revoked_at
+            FROM user_api_keys
+            WHERE credentials_id = ?
+            ORDER BY created_at
+            "#,
+        )
+        .bind(credentials_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to get api keys by credentials id")?;
+
+        Ok(api_keys)
+    }
+
+    async fn touch_api_key(&self, key_id: &str, last_used_at: u64) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE user_api_keys
+            SET last_used_at = ?
+            WHERE key_id = ?
+            "#,
+        )
+        .bind(last_used_at as i64)
+        .bind(key_id)
+        .execute(&self.pool)
+        .await
+        .context("Failed to touch api key")?;
+
+        Ok(())
+    }
+
+    async fn revoke_api_key(
+        &self,
+        credentials_id: &str,
+        key_id: &str,
+        revoked_at: u64,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE user_api_keys
+            SET revoked_at = COALESCE(revoked_at, ?)
+            WHERE key_id = ? AND credentials_id = ?
+            "#,
+        )
+        .bind(revoked_at as i64)
+        .bind(key_id)
+        .bind(credentials_id)
+        .execute(&self.pool)
+        .await
+        .context("Failed to revoke api key")?;
+
+        Ok(result.rows_affected() == 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Expiry, helpers::mysql_configs};
+
+    #[tokio::test]
+    async fn test_mysql_api_key_repo() {
+        let db_config = mysql_configs();
+        let repo = MySqlGateway::new(&db_config.mysql_connection_string()).await;
+
+        let credentials_id = &uuid::Uuid::new_v4().to_string();
+        let (api_key, _) = ApiKey::new(
+            credentials_id,
+            "billing",
+            &["invoices:read", "invoices:write"],
+            Some(&Expiry::Day(30)),
+        );
+        repo.insert_api_key(&api_key).await.unwrap();
+
+        let key_from_repo = repo.get_api_key(&api_key.key_id).await.unwrap().unwrap();
+        assert_eq!(key_from_repo.info(), api_key.info());
+        assert!(repo
+            .get_api_key("0011223344556677")
+            .await
+            .unwrap()
+            .is_none());
+
+        repo.touch_api_key(&api_key.key_id, Expiry::now())
+            .await
+            .unwrap();
+        let keys = repo
+            .get_api_keys_by_credentials_id(credentials_id)
+            .await
+            .unwrap();
+        assert_eq!(keys.len(), 1);
+        assert!(keys[0].last_used_at.is_some());
+
+        // Only the owner can revoke
+        assert!(!repo
+            .revoke_api_key("someone-else", &api_key.key_id, Expiry::now())
+            .await
+            .unwrap());
+        assert!(repo
+            .revoke_api_key(credentials_id, &api_key.key_id, Expiry::now())
+            .await
+            .unwrap());
+        assert!(repo
+            .get_api_key(&api_key.key_id)
+            .await
+            .unwrap()
+            .unwrap()
+            .is_revoked());
+    }
+}
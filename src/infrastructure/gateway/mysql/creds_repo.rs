@@ -3,6 +3,35 @@ use crate::{entity::Credentials, interface::CredentialsRepository};
 use anyhow::{Context, Result};
 use sqlx::{mysql::MySqlRow, FromRow, Row};
 
+/// Tables of keys and tokens owned by credentials, deleted along with them
+const OWNED_TABLES: &[&str] = &["user_api_keys", "user_personal_access_tokens"];
+
+impl MySqlGateway {
+    /// Deletes the credentials matching `column` and everything they own in one transaction
+    async fn delete_credentials_where(&self, column: &str, value: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for table in OWNED_TABLES {
+            sqlx::query(&format!(
+                "DELETE FROM {table} WHERE credentials_id IN (SELECT credentials_id FROM user_credentials WHERE {column} = ?)"
+            ))
+            .bind(value)
+            .execute(&mut *tx)
+            .await
+            .with_context(|| format!("Failed to delete credentials' rows from {table}"))?;
+        }
+
+        sqlx::query(&format!("DELETE FROM user_credentials WHERE {column} = ?"))
+            .bind(value)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to delete credentials")?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
 impl FromRow<'_, MySqlRow> for Credentials {
     fn from_row(row: &MySqlRow) -> sqlx::Result<Self> {
         Ok(Self {
@@ -88,7 +117,7 @@ impl CredentialsRepository for MySqlGateway {
             WHERE user_name = ?
             "#,
         )
-        .bind(&new_password)
+        .bind(new_password)
         .bind(user_name)
         .execute(&self.pool)
         .await
@@ -98,33 +127,15 @@ impl CredentialsRepository for MySqlGateway {
     }
 
     async fn delete_credentials_by_user_name(&self, user_name: &str) -> Result<()> {
-        sqlx::query(
-            r#"
-            DELETE FROM user_credentials
-            WHERE user_name = ?
-            "#,
-        )
-        .bind(user_name)
-        .execute(&self.pool)
-        .await
-        .context("Failed to delete credentials by user name")?;
-
-        Ok(())
+        self.delete_credentials_where("user_name", user_name)
+            .await
+            .context("Failed to delete credentials by user name")
     }
 
     async fn delete_credentials_by_id(&self, id: &str) -> Result<()> {
-        sqlx::query(
-            r#"
-            DELETE FROM user_credentials
-            WHERE credentials_id = ?
-            "#,
-        )
-        .bind(id)
-        .execute(&self.pool)
-        .await
-        .context("Failed to delete credentials by id")?;
-
-        Ok(())
+        self.delete_credentials_where("credentials_id", id)
+            .await
+            .context("Failed to delete credentials by id")
     }
 }
 
@@ -156,7 +167,7 @@ mod tests {
         repo.update_user_name(&credentials.user_name, new_identity)
             .await
             .unwrap();
-        repo.update_user_password(&new_identity, new_password)
+        repo.update_user_password(new_identity, new_password)
             .await
             .unwrap();
 
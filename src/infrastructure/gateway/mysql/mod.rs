@@ -1,7 +1,13 @@
+mod api_key_repo;
 mod creds_repo;
+mod personal_access_token_repo;
+mod refresh_token_repo;
+mod remember_token_repo;
+mod revocation_repo;
 mod session_repo;
 use sqlx::mysql::MySqlPool;
 
+#[derive(Clone)]
 pub struct MySqlGateway {
     pub pool: MySqlPool,
 }
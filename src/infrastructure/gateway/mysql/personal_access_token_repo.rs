@@ -0,0 +1,149 @@
+use super::MySqlGateway;
+use crate::{entity::PersonalAccessToken, interface::PersonalAccessTokenRepository};
+use anyhow::{Context, Result};
+use sqlx::{mysql::MySqlRow, FromRow, Row};
+
+impl FromRow<'_, MySqlRow> for PersonalAccessToken {
+    fn from_row(row: &MySqlRow) -> sqlx::Result<Self> {
+        let scopes: String = row.try_get("scopes")?;
+
+        Ok(Self {
+            token_id: row.try_get("token_id")?,
+            hashed_token: row.try_get("hashed_token")?,
+            token_hint: row.try_get("token_hint")?,
+            credentials_id: row.try_get("credentials_id")?,
+            name: row.try_get("name")?,
+            scopes: scopes.split_whitespace().map(str::to_string).collect(),
+            created_at: row.try_get("created_at")?,
+            expires_at: row.try_get("expires_at")?,
+            last_used_at: row.try_get("last_used_at")?,
+            revoked_at: row.try_get("revoked_at")?,
+        })
+    }
+}
+
+impl PersonalAccessTokenRepository for MySqlGateway {
+    async fn insert_personal_access_token(&self, token: &PersonalAccessToken) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO user_personal_access_tokens (token_id, hashed_token, token_hint, credentials_id, name, scopes, created_at, expires_at, last_used_at, revoked_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?);
+            "#,
+        )
+        .bind(token.token_id.as_str())
+        .bind(token.hashed_token.as_str())
+        .bind(token.token_hint.as_str())
+        .bind(token.credentials_id.as_str())
+        .bind(token.name.as_str())
+        .bind(token.scopes.join(" "))
+        .bind(token.created_at as i64)
+        .bind(token.expires_at.map(|expires_at| expires_at as i64))
+        .bind(token.last_used_at.map(|last_used_at| last_used_at as i64))
+        .bind(token.revoked_at.map(|revoked_at| revoked_at as i64))
+        .execute(&self.pool)
+        .await
+        .context("Failed to store personal access token")?;
+
+        Ok(())
+    }
+
+    async fn get_personal_access_token_by_hash(
+        &self,
+        hashed_token: &str,
+    ) -> Result<Option<PersonalAccessToken>> {
+        let token: Option<PersonalAccessToken> = sqlx::query_as(
+            r#"
+            SELECT token_id, hashed_token, token_hint, credentials_id, name, scopes, created_at, expires_at, last_used_at, revoked_at
+            FROM user_personal_access_tokens
+            WHERE hashed_token = ?
+            "#,
+        )
+        .bind(hashed_token)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to get personal access token by hash")?;
+
+        Ok(token)
+    }
+
+    async fn get_personal_access_tokens_by_credentials_id(
+        &self,
+        credentials_id: &str,
+    ) -> Result<Vec<PersonalAccessToken>> {
+        let tokens: Vec<PersonalAccessToken> = sqlx::query_as(
+            r#"
+            SELECT token_id, hashed_token, token_hint, credentials_id, name, scopes, created_at, expires_at, last_used_at, revoked_at
+            FROM user_personal_access_tokens
+            WHERE credentials_id = ?
+            ORDER BY created_at
+            "#,
+        )
+        .bind(credentials_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to get personal access tokens by credentials id")?;
+
+        Ok(tokens)
+    }
+
+    async fn touch_personal_access_token(&self, token_id: &str, last_used_at: u64) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE user_personal_access_tokens
+            SET last_used_at = ?
+            WHERE token_id = ?
+            "#,
+        )
+        .bind(last_used_at as i64)
+        .bind(token_id)
+        .execute(&self.pool)
+        .await
+        .context("Failed to touch personal access token")?;
+
+        Ok(())
+    }
+
+    async fn revoke_personal_access_token(
+        &self,
+        credentials_id: &str,
+        token_id: &str,
+        revoked_at: u64,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE user_personal_access_tokens
+            SET revoked_at = COALESCE(revoked_at, ?)
+            WHERE token_id = ? AND credentials_id = ?
+            "#,
+        )
+        .bind(revoked_at as i64)
+        .bind(token_id)
+        .bind(credentials_id)
+        .execute(&self.pool)
+        .await
+        .context("Failed to revoke personal access token")?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn revoke_personal_access_tokens_by_credentials_id(
+        &self,
+        credentials_id: &str,
+        revoked_at: u64,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE user_personal_access_tokens
+            SET revoked_at = ?
+            WHERE credentials_id = ? AND revoked_at IS NULL
+            "#,
+        )
+        .bind(revoked_at as i64)
+        .bind(credentials_id)
+        .execute(&self.pool)
+        .await
+        .context("Failed to revoke personal access tokens by credentials id")?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,194 @@
+use super::MySqlGateway;
+use crate::{entity::RefreshToken, interface::RefreshTokenRepository};
+use anyhow::{Context, Result};
+use sqlx::{mysql::MySqlRow, FromRow, Row};
+
+impl FromRow<'_, MySqlRow> for RefreshToken {
+    fn from_row(row: &MySqlRow) -> sqlx::Result<Self> {
+        Ok(Self {
+            token_id: row.try_get("token_id")?,
+            family_id: row.try_get("family_id")?,
+            user_id: row.try_get("user_id")?,
+            created_at: row.try_get("created_at")?,
+            expires_at: row.try_get("expires_at")?,
+            family_expires_at: row.try_get("family_expires_at")?,
+            rotated_at: row.try_get("rotated_at")?,
+        })
+    }
+}
+
+fn insert_refresh_token_query(
+    token: &RefreshToken,
+) -> sqlx::query::Query<'_, sqlx::MySql, sqlx::mysql::MySqlArguments> {
+    sqlx::query(
+        r#"
+        INSERT INTO user_refresh_tokens (token_id, family_id, user_id, created_at, expires_at, family_expires_at, rotated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?);
+        "#,
+    )
+    .bind(token.token_id.as_str())
+    .bind(token.family_id.as_str())
+    .bind(token.user_id.as_str())
+    .bind(token.created_at as i64)
+    .bind(token.expires_at as i64)
+    .bind(token.family_expires_at as i64)
+    .bind(token.rotated_at.map(|rotated_at| rotated_at as i64))
+}
+
+impl RefreshTokenRepository for MySqlGateway {
+    async fn insert_refresh_token(&self, token: &RefreshToken) -> Result<()> {
+        insert_refresh_token_query(token)
+            .execute(&self.pool)
+            .await
+            .context("Failed to store refresh token")?;
+
+        Ok(())
+    }
+
+    async fn get_refresh_token(&self, token_id: &str) -> Result<RefreshToken> {
+        let token: RefreshToken = sqlx::query_as(
+            r#"
+            SELECT token_id, family_id, user_id, created_at, expires_at, family_expires_at, rotated_at
+            FROM user_refresh_tokens
+            WHERE token_id = ?
+            "#,
+        )
+        .bind(token_id)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to get refresh token by id")?;
+
+        Ok(token)
+    }
+
+    async fn rotate_refresh_token(
+        &self,
+        token_id: &str,
+        rotated_at: u64,
+        successor: &RefreshToken,
+    ) -> Result<bool> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .context("Failed to begin refresh token rotation")?;
+
+        let retired = sqlx::query(
+            r#"
+            UPDATE user_refresh_tokens
+            SET rotated_at = ?
+            WHERE token_id = ? AND rotated_at IS NULL
+            "#,
+        )
+        .bind(rotated_at as i64)
+        .bind(token_id)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to retire refresh token")?;
+
+        if retired.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        insert_refresh_token_query(successor)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to store rotated refresh token")?;
+
+        tx.commit()
+            .await
+            .context("Failed to commit refresh token rotation")?;
+
+        Ok(true)
+    }
+
+    async fn delete_refresh_token_family(&self, family_id: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            DELETE FROM user_refresh_tokens
+            WHERE family_id = ?
+            "#,
+        )
+        .bind(family_id)
+        .execute(&self.pool)
+        .await
+        .context("Failed to delete refresh token family")?;
+
+        Ok(())
+    }
+
+    async fn delete_refresh_tokens_by_user_id(&self, user_id: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            DELETE FROM user_refresh_tokens
+            WHERE user_id = ?
+            "#,
+        )
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .context("Failed to delete refresh tokens by user id")?;
+
+        Ok(())
+    }
+
+    async fn delete_expired_refresh_tokens(&self, now: u64, batch_size: u64) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM user_refresh_tokens
+            WHERE expires_at < ?
+            LIMIT ?
+            "#,
+        )
+        .bind(now as i64)
+        .bind(batch_size)
+        .execute(&self.pool)
+        .await
+        .context("Failed to delete expired refresh tokens")?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Expiry, helpers::mysql_configs};
+
+    #[tokio::test]
+    async fn test_mysql_refresh_token_repo() {
+        let db_config = mysql_configs();
+        let repo = MySqlGateway::new(&db_config.mysql_connection_string()).await;
+
+        let user_id = &uuid::Uuid::new_v4().to_string();
+        let (token, _) = RefreshToken::new(&Expiry::Day(30), user_id);
+        repo.insert_refresh_token(&token).await.unwrap();
+
+        let token_from_repo = repo.get_refresh_token(&token.token_id).await.unwrap();
+        assert_eq!(token_from_repo.family_id, token.family_id);
+        assert!(!token_from_repo.is_rotated());
+
+        // Rotation only succeeds once
+        let (successor, _) = token.rotate(&Expiry::Day(30));
+        assert!(repo
+            .rotate_refresh_token(&token.token_id, Expiry::now(), &successor)
+            .await
+            .unwrap());
+        let (second, _) = token.rotate(&Expiry::Day(30));
+        assert!(!repo
+            .rotate_refresh_token(&token.token_id, Expiry::now(), &second)
+            .await
+            .unwrap());
+        assert!(repo.get_refresh_token(&second.token_id).await.is_err());
+        assert!(repo
+            .get_refresh_token(&token.token_id)
+            .await
+            .unwrap()
+            .is_rotated());
+
+        repo.delete_refresh_token_family(&token.family_id)
+            .await
+            .unwrap();
+        assert!(repo.get_refresh_token(&successor.token_id).await.is_err());
+    }
+}
@@ -0,0 +1,142 @@
+use super::MySqlGateway;
+use crate::{entity::RememberToken, interface::RememberTokenRepository};
+use anyhow::{Context, Result};
+use sqlx::{mysql::MySqlRow, FromRow, Row};
+
+impl FromRow<'_, MySqlRow> for RememberToken {
+    fn from_row(row: &MySqlRow) -> sqlx::Result<Self> {
+        Ok(Self {
+            selector: row.try_get("selector")?,
+            hashed_validator: row.try_get("hashed_validator")?,
+            user_id: row.try_get("user_id")?,
+            created_at: row.try_get("created_at")?,
+            expires_at: row.try_get("expires_at")?,
+        })
+    }
+}
+
+impl RememberTokenRepository for MySqlGateway {
+    async fn insert_remember_token(&self, token: &RememberToken) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO user_remember_tokens (selector, hashed_validator, user_id, created_at, expires_at)
+            VALUES (?, ?, ?, ?, ?);
+            "#,
+        )
+        .bind(token.selector.as_str())
+        .bind(token.hashed_validator.as_str())
+        .bind(token.user_id.as_str())
+        .bind(token.created_at as i64)
+        .bind(token.expires_at as i64)
+        .execute(&self.pool)
+        .await
+        .context("Failed to store remember token")?;
+
+        Ok(())
+    }
+
+    async fn get_remember_token(&self, selector: &str) -> Result<RememberToken> {
+        let token: RememberToken = sqlx::query_as(
+            r#"
+            SELECT selector, hashed_validator, user_id, created_at, expires_at
+            FROM user_remember_tokens
+            WHERE selector = ?
+            "#,
+        )
+        .bind(selector)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to get remember token by selector")?;
+
+        Ok(token)
+    }
+
+    async fn rotate_remember_token(
+        &self,
+        selector: &str,
+        hashed_validator: &str,
+        new_hashed_validator: &str,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE user_remember_tokens
+            SET hashed_validator = ?
+            WHERE selector = ? AND hashed_validator = ?
+            "#,
+        )
+        .bind(new_hashed_validator)
+        .bind(selector)
+        .bind(hashed_validator)
+        .execute(&self.pool)
+        .await
+        .context("Failed to rotate remember token")?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn delete_remember_token(&self, selector: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            DELETE FROM user_remember_tokens
+            WHERE selector = ?
+            "#,
+        )
+        .bind(selector)
+        .execute(&self.pool)
+        .await
+        .context("Failed to delete remember token")?;
+
+        Ok(())
+    }
+
+    async fn delete_remember_tokens_by_user_id(&self, user_id: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            DELETE FROM user_remember_tokens
+            WHERE user_id = ?
+            "#,
+        )
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .context("Failed to delete remember tokens by user id")?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Expiry, helpers::mysql_configs};
+
+    #[tokio::test]
+    async fn test_mysql_remember_token_repo() {
+        let db_config = mysql_configs();
+        let repo = MySqlGateway::new(&db_config.mysql_connection_string()).await;
+
+        let user_id = &uuid::Uuid::new_v4().to_string();
+        let (mut token, _) = RememberToken::new(&Expiry::Day(30), user_id);
+        repo.insert_remember_token(&token).await.unwrap();
+
+        let token_from_repo = repo.get_remember_token(&token.selector).await.unwrap();
+        assert_eq!(token_from_repo.hashed_validator, token.hashed_validator);
+
+        // Rotation only succeeds against the current validator
+        let old_hash = token.hashed_validator.clone();
+        token.rotate();
+        assert!(repo
+            .rotate_remember_token(&token.selector, &old_hash, &token.hashed_validator)
+            .await
+            .unwrap());
+        assert!(!repo
+            .rotate_remember_token(&token.selector, &old_hash, &token.hashed_validator)
+            .await
+            .unwrap());
+
+        repo.delete_remember_tokens_by_user_id(user_id)
+            .await
+            .unwrap();
+        assert!(repo.get_remember_token(&token.selector).await.is_err());
+    }
+}
@@ -0,0 +1,164 @@
+use super::MySqlGateway;
+use crate::interface::RevocationRepository;
+use anyhow::{Context, Result};
+use sqlx::Row;
+
+impl RevocationRepository for MySqlGateway {
+    async fn revoke_token(&self, jti: &str, expires_at: u64) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO revoked_tokens (jti, expires_at)
+            VALUES (?, ?)
+            ON DUPLICATE KEY UPDATE expires_at = GREATEST(expires_at, VALUES(expires_at));
+            "#,
+        )
+        .bind(jti)
+        .bind(expires_at as i64)
+        .execute(&self.pool)
+        .await
+        .context("Failed to revoke token")?;
+
+        Ok(())
+    }
+
+    async fn is_token_revoked(&self, jti: &str) -> Result<bool> {
+        let revoked = sqlx::query(
+            r#"
+            SELECT jti
+            FROM revoked_tokens
+            WHERE jti = ?
+            "#,
+        )
+        .bind(jti)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to check token revocation")?;
+
+        Ok(revoked.is_some())
+    }
+
+    async fn revoke_user_tokens_before(
+        &self,
+        user_id: &str,
+        issued_before: u64,
+        expires_at: u64,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO revoked_user_tokens (user_id, revoked_before, expires_at)
+            VALUES (?, ?, ?)
+            ON DUPLICATE KEY UPDATE
+                revoked_before = GREATEST(revoked_before, VALUES(revoked_before)),
+                expires_at = GREATEST(expires_at, VALUES(expires_at));
+            "#,
+        )
+        .bind(user_id)
+        .bind(issued_before as i64)
+        .bind(expires_at as i64)
+        .execute(&self.pool)
+        .await
+        .context("Failed to revoke user tokens")?;
+
+        Ok(())
+    }
+
+    async fn get_user_revoked_before(&self, user_id: &str) -> Result<Option<u64>> {
+        let row = sqlx::query(
+            r#"
+            SELECT revoked_before
+            FROM revoked_user_tokens
+            WHERE user_id = ?
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to get user token revocation")?;
+
+        Ok(match row {
+            Some(row) => Some(row.try_get("revoked_before")?),
+            None => None,
+        })
+    }
+
+    async fn delete_expired_revocations(&self, now: u64, batch_size: u64) -> Result<u64> {
+        let tokens = sqlx::query(
+            r#"
+            DELETE FROM revoked_tokens
+            WHERE expires_at < ?
+            LIMIT ?
+            "#,
+        )
+        .bind(now as i64)
+        .bind(batch_size)
+        .execute(&self.pool)
+        .await
+        .context("Failed to delete expired token revocations")?;
+
+        // Users only get what is left of the batch, so a full batch means there may be more
+        let remaining = batch_size - tokens.rows_affected();
+        if remaining == 0 {
+            return Ok(tokens.rows_affected());
+        }
+
+        let users = sqlx::query(
+            r#"
+            DELETE FROM revoked_user_tokens
+            WHERE expires_at < ?
+            LIMIT ?
+            "#,
+        )
+        .bind(now as i64)
+        .bind(remaining)
+        .execute(&self.pool)
+        .await
+        .context("Failed to delete expired user token revocations")?;
+
+        Ok(tokens.rows_affected() + users.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Expiry, helpers::mysql_configs};
+
+    #[tokio::test]
+    async fn test_mysql_revocation_repo() {
+        let db_config = mysql_configs();
+        let repo = MySqlGateway::new(&db_config.mysql_connection_string()).await;
+
+        let jti = &uuid::Uuid::new_v4().to_string();
+        assert!(!repo.is_token_revoked(jti).await.unwrap());
+        repo.revoke_token(jti, Expiry::now() + 60).await.unwrap();
+        assert!(repo.is_token_revoked(jti).await.unwrap());
+
+        // The latest cutoff wins
+        let user_id = &uuid::Uuid::new_v4().to_string();
+        assert_eq!(repo.get_user_revoked_before(user_id).await.unwrap(), None);
+        repo.revoke_user_tokens_before(user_id, 200, Expiry::now() + 60)
+            .await
+            .unwrap();
+        repo.revoke_user_tokens_before(user_id, 100, Expiry::now() + 60)
+            .await
+            .unwrap();
+        assert_eq!(
+            repo.get_user_revoked_before(user_id).await.unwrap(),
+            Some(200)
+        );
+
+        // Expired revocations are cleaned up
+        let expired_jti = &uuid::Uuid::new_v4().to_string();
+        repo.revoke_token(expired_jti, Expiry::now() - 10)
+            .await
+            .unwrap();
+        assert!(
+            repo.delete_expired_revocations(Expiry::now(), 1000)
+                .await
+                .unwrap()
+                >= 1
+        );
+        assert!(!repo.is_token_revoked(expired_jti).await.unwrap());
+        assert!(repo.is_token_revoked(jti).await.unwrap());
+    }
+}
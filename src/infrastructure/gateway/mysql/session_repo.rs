@@ -1,9 +1,16 @@
 use crate::{
-    entity::{Session, SessionToken},
+    config::{Expiry, SessionLimit, SessionLimitPolicy},
+    entity::{Session, SessionData, SessionId, FLASH_KEY},
+    error::SessionError,
     interface::SessionRepository,
 };
 use anyhow::{Context, Result};
-use sqlx::{mysql::MySqlRow, FromRow, Row};
+use sqlx::{
+    mysql::{MySqlArguments, MySqlRow},
+    query::Query,
+    FromRow, MySql, Row, Transaction,
+};
+use std::future::Future;
 
 use super::MySqlGateway;
 
@@ -11,38 +18,276 @@ impl FromRow<'_, MySqlRow> for Session {
     fn from_row(row: &MySqlRow) -> sqlx::Result<Self> {
         Ok(Self {
             session_id: row.try_get("session_id")?,
+            token: None,
             created_at: row.try_get("created_at")?,
             expires_at: row.try_get("expires_at")?,
+            absolute_expires_at: row.try_get("absolute_expires_at")?,
+            idle_timeout: row.try_get("idle_timeout")?,
+            last_seen_at: row.try_get("last_seen_at")?,
+            rotated_at: row.try_get("rotated_at")?,
+            ip_address: row.try_get("ip_address")?,
+            user_agent: row.try_get("user_agent")?,
+            device_label: row.try_get("device_label")?,
+            data: match row.try_get::<Option<String>, _>("data")? {
+                Some(json) => {
+                    SessionData::from_json(&json).map_err(|e| sqlx::Error::ColumnDecode {
+                        index: "data".to_string(),
+                        source: e.into(),
+                    })?
+                }
+                None => SessionData::default(),
+            },
+            flashes: Vec::new(),
             user_id: row.try_get("user_id")?,
             csrf_token: row.try_get("csrf_token")?,
         })
     }
 }
 
+fn insert_session_query(session: &Session) -> Query<'_, MySql, MySqlArguments> {
+    sqlx::query(
+        r#"
+        INSERT INTO user_sessions (session_id, created_at, expires_at, absolute_expires_at, idle_timeout, last_seen_at, rotated_at, ip_address, user_agent, device_label, data, user_id, csrf_token)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?);
+        "#,
+    )
+    .bind(session.session_id.as_str())
+    .bind(session.created_at as i64) // Converting usize to i64 for compatibility
+    .bind(session.expires_at as i64)
+    .bind(session.absolute_expires_at as i64)
+    .bind(session.idle_timeout as i64)
+    .bind(session.last_seen_at as i64)
+    .bind(session.rotated_at.map(|rotated_at| rotated_at as i64))
+    .bind(session.ip_address.as_deref())
+    .bind(session.user_agent.as_deref())
+    .bind(session.device_label.as_deref())
+    .bind(session.data.to_json())
+    .bind(session.user_id.as_deref())
+    .bind(session.csrf_token.as_str())
+}
+
+/// Times a limited insert or rotation is retried after losing a deadlock to a concurrent login
+const LIMITED_INSERT_ATTEMPTS: usize = 3;
+
+/// SQLSTATE MySql reports for deadlocks and lock wait serialization failures
+const SERIALIZATION_FAILURE: &str = "40001";
+
+/// How a rotation attempt ended, short of a database error
+enum Rotation {
+    Rotated,
+    /// The old session is missing or was already rotated
+    NotFound,
+    /// The session limit policy rejected the new session
+    LimitExceeded {
+        max_sessions: usize,
+    },
+}
+
+/// Runs `attempt` again while it loses deadlocks, up to `LIMITED_INSERT_ATTEMPTS` times
+async fn retry_serialization_failures<T, F, Fut>(mut attempt: F) -> Result<T, sqlx::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, sqlx::Error>>,
+{
+    let mut attempts = 1;
+    loop {
+        match attempt().await {
+            Err(sqlx::Error::Database(e))
+                if e.code().as_deref() == Some(SERIALIZATION_FAILURE)
+                    && attempts < LIMITED_INSERT_ATTEMPTS =>
+            {
+                attempts += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Locks the user's sessions and makes room for one more per the policy, returning false when
+/// the policy rejects it. The locking read takes gap locks on the user_id index, so concurrent
+/// logins for the same user serialize on it, and the loser of any deadlock is retried.
+async fn make_room(
+    tx: &mut Transaction<'_, MySql>,
+    user_id: &str,
+    limit: &SessionLimit,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query(
+        r#"
+        DELETE FROM user_sessions
+        WHERE user_id = ? AND expires_at < ?
+        "#,
+    )
+    .bind(user_id)
+    .bind(Expiry::now() as i64)
+    .execute(&mut **tx)
+    .await?;
+
+    let session_ids: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT session_id
+        FROM user_sessions
+        WHERE user_id = ?
+        ORDER BY last_seen_at, created_at
+        FOR UPDATE
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(&mut **tx)
+    .await?;
+
+    if session_ids.len() >= limit.max_sessions {
+        if limit.policy == SessionLimitPolicy::Reject {
+            return Ok(false);
+        }
+
+        let evict_count = session_ids.len() + 1 - limit.max_sessions.max(1);
+        for session_id in session_ids.iter().take(evict_count) {
+            sqlx::query(
+                r#"
+                DELETE FROM user_sessions
+                WHERE session_id = ?
+                "#,
+            )
+            .bind(session_id)
+            .execute(&mut **tx)
+            .await?;
+        }
+    }
+
+    Ok(true)
+}
+
+impl MySqlGateway {
+    /// Makes room or rejects per the policy, then inserts, returning false when the policy
+    /// rejected the session
+    async fn try_insert_session_with_limit(
+        &self,
+        session: &Session,
+        user_id: &str,
+        limit: &SessionLimit,
+    ) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        if !make_room(&mut tx, user_id, limit).await? {
+            tx.rollback().await?;
+            return Ok(false);
+        }
+
+        insert_session_query(session).execute(&mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    /// Makes room for the new session when given a limit, inserts it and retires the old one,
+    /// all in one transaction. Queued flash messages move to the new session only, so a request
+    /// on the old token in its grace window does not show them a second time.
+    async fn try_rotate_session(
+        &self,
+        old_session_id: &SessionId,
+        new_session: &Session,
+        grace_expires_at: Option<u64>,
+        limit: Option<&SessionLimit>,
+    ) -> Result<Rotation, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        if let (Some(limit), Some(user_id)) = (limit, new_session.user_id.as_deref()) {
+            if !make_room(&mut tx, user_id, limit).await? {
+                tx.rollback().await?;
+                return Ok(Rotation::LimitExceeded {
+                    max_sessions: limit.max_sessions,
+                });
+            }
+        }
+
+        insert_session_query(new_session).execute(&mut *tx).await?;
+
+        let retired = match grace_expires_at {
+            Some(grace_expires_at) => {
+                sqlx::query(
+                    r#"
+                    UPDATE user_sessions
+                    SET expires_at = LEAST(expires_at, ?),
+                        absolute_expires_at = LEAST(absolute_expires_at, ?),
+                        idle_timeout = 0,
+                        rotated_at = ?,
+                        data = JSON_REMOVE(data, ?)
+                    WHERE session_id = ? AND rotated_at IS NULL
+                    "#,
+                )
+                .bind(grace_expires_at as i64)
+                .bind(grace_expires_at as i64)
+                .bind(Expiry::now() as i64)
+                .bind(format!("$.{FLASH_KEY}"))
+                .bind(old_session_id)
+                .execute(&mut *tx)
+                .await?
+            }
+            None => {
+                sqlx::query(
+                    r#"
+                    DELETE FROM user_sessions
+                    WHERE session_id = ? AND rotated_at IS NULL
+                    "#,
+                )
+                .bind(old_session_id)
+                .execute(&mut *tx)
+                .await?
+            }
+        };
+
+        if retired.rows_affected() == 0 {
+            tx.rollback().await?;
+            return Ok(Rotation::NotFound);
+        }
+
+        tx.commit().await?;
+
+        Ok(Rotation::Rotated)
+    }
+}
+
 impl SessionRepository for MySqlGateway {
     async fn insert_session(&self, session: &Session) -> Result<()> {
-        sqlx::query(
-            r#"
-            INSERT INTO user_sessions (session_id, created_at, expires_at, user_id, csrf_token)
-            VALUES (?, ?, ?, ?, ?);
-            "#,
-        )
-        .bind(session.session_id.as_str())
-        .bind(session.created_at as i64) // Converting usize to i64 for compatibility
-        .bind(session.expires_at as i64)
-        .bind(session.user_id.as_str())
-        .bind(session.csrf_token.as_str())
-        .execute(&self.pool)
+        insert_session_query(session)
+            .execute(&self.pool)
+            .await
+            .context("Failed to store session in MySql")?;
+
+        Ok(())
+    }
+
+    async fn insert_session_with_limit(
+        &self,
+        session: &Session,
+        limit: &SessionLimit,
+    ) -> Result<()> {
+        // Guests hold no user to limit
+        let Some(user_id) = session.user_id.as_deref() else {
+            return self.insert_session(session).await;
+        };
+
+        let inserted = retry_serialization_failures(|| {
+            self.try_insert_session_with_limit(session, user_id, limit)
+        })
         .await
         .context("Failed to store session in MySql")?;
 
-        Ok(())
+        if inserted {
+            Ok(())
+        } else {
+            Err(SessionError::LimitExceeded {
+                max_sessions: limit.max_sessions,
+            }
+            .into())
+        }
     }
 
-    async fn get_session_by_id(&self, session_id: &SessionToken) -> Result<Session> {
+    async fn get_session_by_id(&self, session_id: &SessionId) -> Result<Session> {
         let session: Session = sqlx::query_as(
             r#"
-            SELECT session_id, created_at, expires_at, user_id, csrf_token
+            SELECT session_id, created_at, expires_at, absolute_expires_at, idle_timeout, last_seen_at,
+                rotated_at, ip_address, user_agent, device_label, data, user_id, csrf_token
             FROM user_sessions
             WHERE session_id = ?
             "#,
@@ -55,7 +300,84 @@ impl SessionRepository for MySqlGateway {
         Ok(session)
     }
 
-    async fn delete_session(&self, session_id: &SessionToken) -> Result<()> {
+    async fn get_sessions_by_user_id(&self, user_id: &str) -> Result<Vec<Session>> {
+        let sessions: Vec<Session> = sqlx::query_as(
+            r#"
+            SELECT session_id, created_at, expires_at, absolute_expires_at, idle_timeout, last_seen_at,
+                rotated_at, ip_address, user_agent, device_label, data, user_id, csrf_token
+            FROM user_sessions
+            WHERE user_id = ?
+            ORDER BY created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to get sessions by user id")?;
+
+        Ok(sessions)
+    }
+
+    async fn touch_session(&self, session: &Session) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE user_sessions
+            SET expires_at = ?, last_seen_at = ?
+            WHERE session_id = ?
+            "#,
+        )
+        .bind(session.expires_at as i64)
+        .bind(session.last_seen_at as i64)
+        .bind(session.session_id.as_str())
+        .execute(&self.pool)
+        .await
+        .context("Failed to touch session")?;
+
+        Ok(())
+    }
+
+    async fn update_session_data(&self, session: &Session) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE user_sessions
+            SET data = ?
+            WHERE session_id = ?
+            "#,
+        )
+        .bind(session.data.to_json())
+        .bind(session.session_id.as_str())
+        .execute(&self.pool)
+        .await
+        .context("Failed to update session data")?;
+
+        Ok(())
+    }
+
+    async fn rotate_session(
+        &self,
+        old_session_id: &SessionId,
+        new_session: &Session,
+        grace_expires_at: Option<u64>,
+        limit: Option<&SessionLimit>,
+    ) -> Result<()> {
+        let rotation = retry_serialization_failures(|| {
+            self.try_rotate_session(old_session_id, new_session, grace_expires_at, limit)
+        })
+        .await
+        .context("Failed to rotate session")?;
+
+        match rotation {
+            Rotation::Rotated => Ok(()),
+            Rotation::NotFound => Err(anyhow::anyhow!(
+                "Failed to rotate session, session not found or already rotated"
+            )),
+            Rotation::LimitExceeded { max_sessions } => {
+                Err(SessionError::LimitExceeded { max_sessions }.into())
+            }
+        }
+    }
+
+    async fn delete_session(&self, session_id: &SessionId) -> Result<()> {
         sqlx::query(
             r#"
             DELETE FROM user_sessions 
@@ -69,29 +391,182 @@ impl SessionRepository for MySqlGateway {
 
         Ok(())
     }
+
+    async fn delete_expired_sessions(&self, now: u64, batch_size: u64) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM user_sessions
+            WHERE expires_at < ?
+            LIMIT ?
+            "#,
+        )
+        .bind(now as i64)
+        .bind(batch_size)
+        .execute(&self.pool)
+        .await
+        .context("Failed to delete expired sessions")?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn delete_sessions_by_user_id(&self, user_id: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            DELETE FROM user_sessions
+            WHERE user_id = ?
+            "#,
+        )
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .context("Failed to delete sessions by user id")?;
+
+        Ok(())
+    }
+
+    async fn delete_sessions_except(&self, user_id: &str, keep: &SessionId) -> Result<()> {
+        sqlx::query(
+            r#"
+            DELETE FROM user_sessions
+            WHERE user_id = ? AND session_id <> ?
+            "#,
+        )
+        .bind(user_id)
+        .bind(keep)
+        .execute(&self.pool)
+        .await
+        .context("Failed to delete other sessions for user")?;
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{config::Expiry, helpers::mysql_configs};
+    use crate::{entity::SessionMetadata, helpers::mysql_configs};
 
     #[tokio::test]
     async fn test_mysql_session_repo() {
         let db_config = mysql_configs();
         let repo = MySqlGateway::new(&db_config.mysql_connection_string()).await;
 
-        let session = &Session::new(&Expiry::Day(1), "848hfhs0-88ryh-eohrnf-odsiru");
+        let metadata = SessionMetadata::new(Some("203.0.113.7"), Some("curl/8.4.0"));
+        let session =
+            &Session::new(&Expiry::Day(1), "848hfhs0-88ryh-eohrnf-odsiru").with_metadata(&metadata);
         let query = repo.insert_session(session).await;
         assert!(query.is_ok());
 
         let session_from_repo = repo.get_session_by_id(&session.session_id).await.unwrap();
-        assert_eq!(session_from_repo.is_expired(), false);
+        assert!(!session_from_repo.is_expired());
         assert_eq!(session_from_repo.session_id, session.session_id);
         assert_eq!(session_from_repo.csrf_token, session.csrf_token);
+        assert_eq!(session_from_repo.ip_address.as_deref(), Some("203.0.113.7"));
+        assert_eq!(session_from_repo.device_label.as_deref(), Some("curl"));
+
+        let mut session = session.clone().with_idle_timeout(&Expiry::Second(60));
+        session.expires_at -= 30;
+        assert!(session.touch(10));
+        repo.touch_session(&session).await.unwrap();
+        let session_from_repo = repo.get_session_by_id(&session.session_id).await.unwrap();
+        assert_eq!(session_from_repo.expires_at, session.expires_at);
+        assert_eq!(session_from_repo.last_seen_at, session.last_seen_at);
+
+        session.insert("locale", "en-US").unwrap();
+        repo.update_session_data(&session).await.unwrap();
+        let session_from_repo = repo.get_session_by_id(&session.session_id).await.unwrap();
+        assert_eq!(
+            session_from_repo
+                .get::<String>("locale")
+                .unwrap()
+                .as_deref(),
+            Some("en-US")
+        );
+
+        let rotated = session.rotate();
+        repo.rotate_session(&session.session_id, &rotated, Some(Expiry::now() + 5), None)
+            .await
+            .unwrap();
+        let old_from_repo = repo.get_session_by_id(&session.session_id).await.unwrap();
+        assert!(old_from_repo.absolute_expires_at <= Expiry::now() + 5);
+        assert!(old_from_repo.is_rotated());
+        assert!(repo
+            .rotate_session(&session.session_id, &session.rotate(), None, None)
+            .await
+            .is_err());
+        let rotated_from_repo = repo.get_session_by_id(&rotated.session_id).await.unwrap();
+        assert_eq!(rotated_from_repo.user_id, session.user_id);
+
+        repo.rotate_session(&rotated.session_id, &rotated.rotate(), None, None)
+            .await
+            .unwrap();
+        assert!(repo.get_session_by_id(&rotated.session_id).await.is_err());
 
         repo.delete_session(&session.session_id).await.unwrap();
+
+        // Sessions per user
+        let user_id = &uuid::Uuid::new_v4().to_string();
+        let keep = Session::new(&Expiry::Day(1), user_id);
+        for session in [
+            &keep,
+            &Session::new(&Expiry::Day(1), user_id),
+            &Session::new(&Expiry::Day(1), user_id),
+        ] {
+            repo.insert_session(session).await.unwrap();
+        }
+        assert_eq!(
+            repo.get_sessions_by_user_id(user_id).await.unwrap().len(),
+            3
+        );
+
+        repo.delete_sessions_except(user_id, &keep.session_id)
+            .await
+            .unwrap();
+        let sessions = repo.get_sessions_by_user_id(user_id).await.unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].session_id, keep.session_id);
+
+        repo.delete_sessions_by_user_id(user_id).await.unwrap();
+        assert!(repo
+            .get_sessions_by_user_id(user_id)
+            .await
+            .unwrap()
+            .is_empty());
+
         let session_from_repo = repo.get_session_by_id(&session.session_id).await;
         assert!(session_from_repo.is_err());
+
+        // Session limits
+        let user_id = &uuid::Uuid::new_v4().to_string();
+        let mut limit = SessionLimit {
+            max_sessions: 2,
+            policy: SessionLimitPolicy::EvictOldest,
+        };
+        let mut oldest = Session::new(&Expiry::Day(1), user_id);
+        oldest.last_seen_at -= 10;
+        repo.insert_session_with_limit(&oldest, &limit)
+            .await
+            .unwrap();
+        for _ in 0..2 {
+            let session = Session::new(&Expiry::Day(1), user_id);
+            repo.insert_session_with_limit(&session, &limit)
+                .await
+                .unwrap();
+        }
+        let sessions = repo.get_sessions_by_user_id(user_id).await.unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert!(sessions.iter().all(|s| s.session_id != oldest.session_id));
+
+        limit.policy = SessionLimitPolicy::Reject;
+        let rejected = repo
+            .insert_session_with_limit(&Session::new(&Expiry::Day(1), user_id), &limit)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            rejected.downcast_ref::<SessionError>(),
+            Some(&SessionError::LimitExceeded { max_sessions: 2 })
+        );
+
+        repo.delete_sessions_by_user_id(user_id).await.unwrap();
     }
 }
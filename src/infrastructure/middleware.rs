@@ -0,0 +1,541 @@
+use crate::authorization::parse_bearer;
+use crate::cookie::CookieConfig;
+use crate::domain::entity::Session;
+use crate::infrastructure::gateway::cookie_store::CookieStore;
+use crate::interface::SessionRepository;
+use crate::SessionClient;
+use anyhow::Result;
+use axum::{
+    extract::{FromRequestParts, Request},
+    http::{header, request::Parts, HeaderName, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Response},
+};
+use std::{
+    convert::Infallible,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Header the CSRF layer reads the token from by default
+const DEFAULT_CSRF_HEADER: &str = "x-csrf-token";
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// Where the session token of a request came from, inserted alongside the `Session`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionSource {
+    Cookie,
+    Bearer,
+}
+
+/// Inserted by `SessionLayer` into every request it handles, so `CsrfLayer` can tell a request
+/// without a session from a request that never went through `SessionLayer`
+#[derive(Clone, Copy)]
+struct SessionLayerRan;
+
+/// Validates the session tokens `SessionLayer` finds. Implemented for session clients over any
+/// `SessionRepository`, and over a `CookieStore`, whose resealed cookies the layer sends back.
+pub trait SessionValidator: Send + Sync + 'static {
+    fn validate(&self, session_token: &str) -> impl Future<Output = Result<Session>> + Send;
+}
+
+impl<R: SessionRepository + 'static> SessionValidator for SessionClient<R> {
+    fn validate(&self, session_token: &str) -> impl Future<Output = Result<Session>> + Send {
+        self.validate_session(session_token)
+    }
+}
+
+impl SessionValidator for SessionClient<CookieStore> {
+    fn validate(&self, session_token: &str) -> impl Future<Output = Result<Session>> + Send {
+        self.validate_session(session_token)
+    }
+}
+
+/// Loads the session of every request from the session cookie, or else an `Authorization: Bearer`
+/// header, into the request extensions for `AuthenticatedUser` and `MaybeUser`.
+/// Requests without a valid session carry on without one. When a cookie session was resealed,
+/// e.g. by a `CookieStore` client sliding its expiration, the new cookie is set on the response.
+pub struct SessionLayer<V> {
+    client: Arc<V>,
+    cookie: CookieConfig,
+}
+
+impl<V> Clone for SessionLayer<V> {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            cookie: self.cookie.clone(),
+        }
+    }
+}
+
+impl<V: SessionValidator> SessionLayer<V> {
+    /// Reads the `CookieConfig::session` cookie
+    pub fn new(client: Arc<V>) -> Self {
+        Self {
+            client,
+            cookie: CookieConfig::session(),
+        }
+    }
+
+    pub fn with_cookie(mut self, cookie: CookieConfig) -> Self {
+        self.cookie = cookie;
+        self
+    }
+}
+
+impl<S, V> Layer<S> for SessionLayer<V> {
+    type Service = SessionService<S, V>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SessionService {
+            inner,
+            client: self.client.clone(),
+            cookie: self.cookie.clone(),
+        }
+    }
+}
+
+/// The service made by `SessionLayer`
+pub struct SessionService<S, V> {
+    inner: S,
+    client: Arc<V>,
+    cookie: CookieConfig,
+}
+
+impl<S: Clone, V> Clone for SessionService<S, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            client: self.client.clone(),
+            cookie: self.cookie.clone(),
+        }
+    }
+}
+
+impl<S, V> SessionService<S, V> {
+    /// The session tokens of the request, the cookie first, so a stale cookie does not hide a valid Bearer token
+    fn session_tokens(&self, request: &Request) -> Vec<(String, SessionSource)> {
+        let headers = request.headers();
+        let from_cookie = headers
+            .get_all(header::COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .find_map(|cookie_header| self.cookie.find(cookie_header))
+            .map(|token| (token.to_string(), SessionSource::Cookie));
+        let from_bearer = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|authorization| parse_bearer(authorization).ok())
+            .map(|token| (token.to_string(), SessionSource::Bearer));
+
+        from_cookie.into_iter().chain(from_bearer).collect()
+    }
+}
+
+impl<S, V> Service<Request> for SessionService<S, V>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    V: SessionValidator,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request) -> Self::Future {
+        // Take the service that was driven to readiness, leaving a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let client = self.client.clone();
+        let cookie = self.cookie.clone();
+        let tokens = self.session_tokens(&request);
+
+        Box::pin(async move {
+            let mut resealed_cookie = None;
+            for (token, source) in tokens {
+                if let Ok(session) = client.validate(&token).await {
+                    if source == SessionSource::Cookie {
+                        resealed_cookie = cookie.session_cookie(&session);
+                    }
+                    request.extensions_mut().insert(session);
+                    request.extensions_mut().insert(source);
+                    break;
+                }
+            }
+            request.extensions_mut().insert(SessionLayerRan);
+
+            let mut response = inner.call(request).await?;
+            if let Some(value) = resealed_cookie.and_then(|value| HeaderValue::try_from(value).ok())
+            {
+                response.headers_mut().append(header::SET_COOKIE, value);
+            }
+
+            Ok(response)
+        })
+    }
+}
+
+/// Extracts the logged in user of a request loaded by `SessionLayer`, rejecting with 401
+/// when there is no session or it is a guest session
+#[derive(Clone)]
+pub struct AuthenticatedUser {
+    pub user_id: String,
+    pub session: Session,
+}
+
+impl AuthenticatedUser {
+    fn from_parts(parts: &Parts) -> Option<Self> {
+        let session = parts.extensions.get::<Session>()?;
+
+        Some(Self {
+            user_id: session.user_id.clone()?,
+            session: session.clone(),
+        })
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for AuthenticatedUser {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Self::from_parts(parts).ok_or(StatusCode::UNAUTHORIZED)
+    }
+}
+
+/// Extracts the logged in user when there is one, never rejecting
+#[derive(Clone)]
+pub struct MaybeUser(pub Option<AuthenticatedUser>);
+
+impl<S: Send + Sync> FromRequestParts<S> for MaybeUser {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self(AuthenticatedUser::from_parts(parts)))
+    }
+}
+
+/// Rejects unsafe requests whose session came from a cookie with 403, unless they carry the
+/// session's CSRF token, raw or masked, in a header. Add it before `SessionLayer` so it runs after,
+/// i.e. `.layer(CsrfLayer::new()).layer(SessionLayer::new(client))`, requests that did not go
+/// through `SessionLayer` are answered with 500 rather than let through unchecked.
+/// Bearer requests are not exposed to CSRF, since browsers never attach the header on their own.
+#[derive(Clone)]
+pub struct CsrfLayer {
+    header: HeaderName,
+}
+
+impl CsrfLayer {
+    /// Reads the token from `X-CSRF-Token`
+    pub fn new() -> Self {
+        Self {
+            header: HeaderName::from_static(DEFAULT_CSRF_HEADER),
+        }
+    }
+
+    pub fn with_header(mut self, header: HeaderName) -> Self {
+        self.header = header;
+        self
+    }
+}
+
+impl Default for CsrfLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Layer<S> for CsrfLayer {
+    type Service = CsrfService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CsrfService {
+            inner,
+            header: self.header.clone(),
+        }
+    }
+}
+
+/// The service made by `CsrfLayer`
+#[derive(Clone)]
+pub struct CsrfService<S> {
+    inner: S,
+    header: HeaderName,
+}
+
+impl<S> CsrfService<S> {
+    /// The status to refuse the request with, None when it may proceed
+    fn refusal(&self, request: &Request) -> Option<StatusCode> {
+        if request.extensions().get::<SessionLayerRan>().is_none() {
+            return Some(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+
+        (!self.is_allowed(request)).then_some(StatusCode::FORBIDDEN)
+    }
+
+    fn is_allowed(&self, request: &Request) -> bool {
+        if matches!(
+            *request.method(),
+            Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+        ) {
+            return true;
+        }
+
+        let extensions = request.extensions();
+        let (Some(session), Some(SessionSource::Cookie)) = (
+            extensions.get::<Session>(),
+            extensions.get::<SessionSource>(),
+        ) else {
+            return true;
+        };
+
+        request
+            .headers()
+            .get(&self.header)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|token| session.match_csrf_token(token))
+    }
+}
+
+impl<S> Service<Request> for CsrfService<S>
+where
+    S: Service<Request, Response = Response> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        match self.refusal(&request) {
+            None => Box::pin(self.inner.call(request)),
+            Some(status) => Box::pin(async move { Ok(status.into_response()) }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Expiry;
+    use crate::cookie_store::{CookieKey, CookieKeyRing};
+    use crate::domain::entity::FlashLevel;
+    use crate::memory::MemoryGateway;
+    use axum::{body::Body, middleware::from_fn, middleware::Next, routing::post, Router};
+    use tower::ServiceExt;
+
+    async fn handler(MaybeUser(user): MaybeUser) -> String {
+        user.map(|user| user.user_id).unwrap_or_default()
+    }
+
+    async fn protected(user: AuthenticatedUser) -> String {
+        user.user_id
+    }
+
+    fn app(session: Option<Session>, source: SessionSource) -> Router {
+        Router::new()
+            .route("/", post(handler).get(handler))
+            .route("/protected", post(protected))
+            .layer(CsrfLayer::new())
+            .layer(from_fn(move |mut request: Request, next: Next| {
+                if let Some(session) = session.clone() {
+                    request.extensions_mut().insert(session);
+                    request.extensions_mut().insert(source);
+                }
+                request.extensions_mut().insert(SessionLayerRan);
+                next.run(request)
+            }))
+    }
+
+    fn session_app<V: SessionValidator>(client: Arc<V>) -> Router {
+        Router::new()
+            .route("/", post(handler).get(handler))
+            .layer(CsrfLayer::new())
+            .layer(SessionLayer::new(client))
+    }
+
+    fn session_request(method: Method, cookie: Option<&str>, bearer: Option<&str>) -> Request {
+        let mut request = Request::builder().method(method).uri("/");
+        if let Some(cookie) = cookie {
+            let cookie_name = CookieConfig::session().cookie_name();
+            request = request.header(header::COOKIE, format!("{cookie_name}={cookie}"));
+        }
+        if let Some(bearer) = bearer {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {bearer}"));
+        }
+        request.body(Body::empty()).unwrap()
+    }
+
+    async fn user_id(response: Response) -> String {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    async fn send(app: Router, method: Method, uri: &str, csrf: Option<&str>) -> StatusCode {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(csrf) = csrf {
+            request = request.header("x-csrf-token", csrf);
+        }
+        app.oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_axum_extractors_and_csrf() {
+        let session = Session::new(&Expiry::Day(1), "user-id");
+        let cookie_app = || app(Some(session.clone()), SessionSource::Cookie);
+
+        // Safe methods skip the CSRF check
+        assert_eq!(
+            send(cookie_app(), Method::GET, "/", None).await,
+            StatusCode::OK
+        );
+
+        // Unsafe cookie requests need the raw or masked token
+        assert_eq!(
+            send(cookie_app(), Method::POST, "/", None).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            send(cookie_app(), Method::POST, "/", Some("wrong")).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            send(
+                cookie_app(),
+                Method::POST,
+                "/",
+                Some(&session.masked_csrf_token())
+            )
+            .await,
+            StatusCode::OK
+        );
+
+        // Bearer sessions and anonymous requests are not exposed to CSRF
+        let bearer_app = app(Some(session.clone()), SessionSource::Bearer);
+        assert_eq!(
+            send(bearer_app, Method::POST, "/protected", None).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            send(app(None, SessionSource::Cookie), Method::POST, "/", None).await,
+            StatusCode::OK
+        );
+
+        // Protected routes need a logged in, non guest session
+        assert_eq!(
+            send(
+                app(None, SessionSource::Cookie),
+                Method::POST,
+                "/protected",
+                None
+            )
+            .await,
+            StatusCode::UNAUTHORIZED
+        );
+        let guest_app = app(
+            Some(Session::new_guest(&Expiry::Day(1))),
+            SessionSource::Bearer,
+        );
+        assert_eq!(
+            send(guest_app, Method::POST, "/protected", None).await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[tokio::test]
+    async fn test_session_layer() {
+        let client = Arc::new(SessionClient::new(MemoryGateway::new()));
+        let session = client
+            .start_session("user-id", Expiry::Day(1))
+            .await
+            .unwrap();
+        let token = session.token.clone().unwrap();
+
+        // Sessions load from the cookie, or a Bearer header when the cookie does not validate
+        let response = session_app(client.clone())
+            .oneshot(session_request(Method::GET, Some(&token), None))
+            .await
+            .unwrap();
+        assert_eq!(user_id(response).await, "user-id");
+        let response = session_app(client.clone())
+            .oneshot(session_request(Method::GET, Some("stale"), Some(&token)))
+            .await
+            .unwrap();
+        assert_eq!(user_id(response).await, "user-id");
+        let response = session_app(client.clone())
+            .oneshot(session_request(Method::GET, None, None))
+            .await
+            .unwrap();
+        assert_eq!(user_id(response).await, "");
+
+        // Cookie sessions are held to CSRF checks, Bearer sessions are not
+        let response = session_app(client.clone())
+            .oneshot(session_request(Method::POST, Some(&token), None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = session_app(client.clone())
+            .oneshot(session_request(Method::POST, None, Some(&token)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // A CsrfLayer that runs before SessionLayer fails closed
+        let misordered = Router::new()
+            .route("/", post(handler))
+            .layer(SessionLayer::new(client.clone()))
+            .layer(CsrfLayer::new());
+        let response = misordered
+            .oneshot(session_request(Method::POST, Some(&token), None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[tokio::test]
+    async fn test_session_layer_reseals_cookie_sessions() {
+        let client = Arc::new(SessionClient::new_cookie_client(CookieKeyRing::new(
+            CookieKey::new("k1", &[7; 32]),
+        )));
+        let mut session = client
+            .start_session("user-id", Expiry::Day(1))
+            .await
+            .unwrap();
+        session.push_flash(FlashLevel::Info, "Welcome").unwrap();
+        client.save_session(&mut session).await.unwrap();
+
+        // Taking the flash message changes the session, so the resealed cookie is sent back
+        let response = session_app(client.clone())
+            .oneshot(session_request(
+                Method::GET,
+                Some(session.token.as_deref().unwrap()),
+                None,
+            ))
+            .await
+            .unwrap();
+        let set_cookie = response.headers().get(header::SET_COOKIE).unwrap();
+        let cookie_name = CookieConfig::session().cookie_name();
+        assert!(set_cookie
+            .to_str()
+            .unwrap()
+            .starts_with(&format!("{cookie_name}=")));
+        assert_eq!(user_id(response).await, "user-id");
+    }
+}
@@ -1,2 +1,4 @@
 pub mod gateway;
+#[cfg(feature = "axum")]
+pub mod middleware;
 pub mod services;
@@ -10,3 +10,5 @@ pub use application::*;
 
 mod infrastructure;
 pub use infrastructure::gateway::*;
+#[cfg(feature = "axum")]
+pub use infrastructure::middleware;